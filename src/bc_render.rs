@@ -1,184 +1,796 @@
-use std::os::linux::raw;
+use std::{collections::BTreeSet, fmt};
 
-use crate::common::{Dimension, Node, Tile};
+use crate::{
+    common::{
+        self, Dimension, HAlign, Highlight, LayoutError, Node, Text, TextLine, TextOverflow, Tile,
+        VAlign, ELLIPSIS,
+    },
+    display::DisplayProfile,
+    fonts::FontSet,
+    format::{self, FormatKind},
+};
 
-fn render_bc_widgets(root: &Node) -> (String, String) {
-    match root {
-        Node::V(nodes) | Node::H(nodes) => nodes.iter().map(|node| render_bc_widgets(node)).fold(
-            (String::default(), String::default()),
-            |mut acc, x| {
-                acc.0 += &x.0;
-                acc.1 += &x.1;
-
-                acc
-            },
-        ),
-        Node::HH(split) => {
-            let (l_dyn, l_stat) = render_bc_widgets(&split.first);
-            let (r_dyn, r_stat) = render_bc_widgets(&split.second);
-
-            // TODO: format! ??
-            (l_dyn + &r_dyn, l_stat + &r_stat)
-        }
-        Node::Tile(tile) => {
-            let format_msg = match tile.text.format {
-                Some(format) => format!(
-                    r#"snprintf(message, msg_size, "{format}", data.);"#,
-                    format = format
-                ),
-                None => format!(
-                    r#"snprintf(message, msg_size, "{text}");"#,
-                    text = tile.text.name
-                ),
-            };
+/// Dynamic tiles, drawn by their own method of the view
+struct Draw {
+    /// Name of the method without "draw", like "GpsInView"
+    method: String,
+    /// Texts of the tiles, for the comment
+    names: Vec<&'static str>,
+    /// Code painting each of the tiles
+    paints: Vec<String>,
+    refresh: Dimension,
+    /// Tiles of the same source are updated together
+    source: Option<&'static str>,
+}
 
-            (
-                format!(
-                    r#"// {name}
-display_->enqueueDraw(
-    [&](Paint &paint) {{
-        const int msg_size = 128;
-        char message[msg_size];
+/// Widget owned by the view, constructed in its ctor
+struct Widget {
+    member: String,
+    class: &'static str,
+    args: String,
+}
 
-    {format_msg}
-    paint.DrawStringAt({x}, {y}, message, &Font{font}, COLORED);
+/// Code of the page, before it is put into a class
+#[derive(Default)]
+struct BcWidgets {
+    draws: Vec<Draw>,
+    static_elements: String,
+    /// Covers all static elements, None if page has none
+    static_area: Option<Dimension>,
+    /// Areas merged refreshes must not wipe out, static elements and widgets
+    obstacles: Vec<Dimension>,
+    widgets: Vec<Widget>,
+}
 
-}},
-{{{x0}, {y0}, {x1}, {y1}}});
+impl BcWidgets {
+    fn add_static(&mut self, paint: String, dim: &Dimension) {
+        self.obstacles.push(*dim);
+        self.static_elements += &paint;
+        self.static_area = Some(match self.static_area {
+            Some(area) => area.union(dim),
+            None => *dim,
+        });
+    }
+}
 
-"#,
-                    name = tile.text.name,
-                    format_msg = format_msg,
-                    x = tile.dim.x + tile.text.dim.x,
-                    y = tile.dim.y + tile.text.dim.y,
-                    font = tile.text.font_size.unwrap(),
-                    // Shrink refresh area so static elements will not be wiped out
-                    x0 = tile.dim.x + 1,
-                    y0 = tile.dim.y + 1,
-                    x1 = tile.dim.x + tile.dim.width - 1,
-                    y1 = tile.dim.y + tile.dim.height - 1
-                ),
-                String::default(),
-            )
+fn render_bc_widgets(root: &Node, widgets: &mut BcWidgets) {
+    match root {
+        Node::V(layout) | Node::H(layout) => {
+            for node in &layout.nodes {
+                render_bc_widgets(node, widgets);
+            }
+        }
+        Node::HH(split) | Node::VV(split) => {
+            render_bc_widgets(&split.first, widgets);
+            render_bc_widgets(&split.second, widgets);
+        }
+        // Labels are drawn once, with the lines
+        Node::Tile(tile) if !tile.text.is_dynamic() => {
+            widgets.add_static(render_bc_tile(tile), &tile.content())
         }
-        Node::HorizontalLine(dim) => (
-            String::default(),
+        Node::Tile(tile) => widgets.draws.push(Draw {
+            method: method_name(&tile.text),
+            names: vec![tile.text.name],
+            paints: vec![render_bc_tile(tile)],
+            // Refresh only the content, so static elements will not be wiped out
+            refresh: tile.refresh,
+            source: tile.text.source(),
+        }),
+        Node::HorizontalLine(line) => widgets.add_static(
             format!(
                 r#"    paint.DrawHorizontalLine({x}, {y}, {line_width}, COLORED);
-                "#,
-                x = dim.x,
-                y = dim.y,
-                line_width = dim.width
+"#,
+                x = line.dim.x,
+                y = line.dim.y,
+                line_width = line.dim.width
             ),
+            &line.dim,
         ),
-        Node::VerticalLine(dim) => (
-            String::default(),
+        Node::VerticalLine(line) => widgets.add_static(
             format!(
                 r#"    paint.DrawVerticalLine({x}, {y}, {line_height}, COLORED);
-                "#,
-                x = dim.x,
-                y = dim.y,
-                line_height = dim.height
+"#,
+                x = line.dim.x,
+                y = line.dim.y,
+                line_height = line.dim.height
             ),
+            &line.dim,
         ),
         Node::VListWidget(list) => {
-            let raw_elements: Vec<_> = list.elements.iter().map(|tile| tile.text.name).collect();
-            // That magic in .replace is ... magic
-            let raw_elements = format!("{:?}", raw_elements).replace(&['[', ']'][..], "");
-            (
+            let raw_elements: Vec<_> = list
+                .elements
+                .iter()
+                .map(|tile| c_string(tile.text.name))
+                .collect();
+            let raw_elements = raw_elements.join(", ");
+            let refresh = list.refresh;
+            widgets.obstacles.push(refresh);
+            let member = format!("list{}_", widgets.widgets.len());
+            widgets.widgets.push(Widget {
+                member,
+                class: "VListWidget",
+                args: format!(
+                    "display, {{{activities}}}, Font{font}, {{{x0}, {y0}, {x1}, {y1}}}, {visible}, {selected}, {highlight}",
+                    activities = raw_elements,
+                    font = list.font.unwrap(),
+                    x0 = refresh.x,
+                    y0 = refresh.y,
+                    x1 = refresh.x + refresh.width,
+                    y1 = refresh.y + refresh.height,
+                    visible = list.visible_elements,
+                    // Nothing selected is -1
+                    selected = list.selected.map_or(-1, |idx| idx as isize),
+                    highlight = match list.highlight {
+                        Highlight::Inverted => "Highlight::Inverted",
+                        Highlight::Frame => "Highlight::Frame",
+                    }
+                ),
+            });
+        }
+    }
+}
+
+/// Code painting the tile on the display
+fn render_bc_tile(tile: &Tile) -> String {
+    let font = tile.text.font.unwrap();
+    match tile.text.wrap {
+        // Wrapped text is not formatted, every line is drawn as is
+        true => tile
+            .text
+            .lines
+            .iter()
+            .map(|line| {
                 format!(
-                    r#"
-// CTOR
-VListWidget(display, {{{activities}}}, Font{font}, {{{x0}, {y0}, {x1}, {y1}}})
+                    r#"    paint.DrawStringAt({x}, {y}, {text}, &Font{font}, COLORED);
+"#,
+                    x = tile.dim.x + line.x,
+                    y = tile.dim.y + line.y,
+                    text = c_string(&line.text),
+                    font = font
+                )
+            })
+            .collect(),
+        // Constant label needs no message buffer
+        false if !tile.text.is_dynamic() => format!(
+            r#"    paint.DrawStringAt({x}, {y}, {text}, &Font{font}, COLORED);
+"#,
+            x = tile.dim.x + tile.text.dim.x,
+            y = tile.dim.y + tile.text.dim.y,
+            text = c_string(&tile.text.shown()),
+            font = font
+        ),
+        false => {
+            let mut format_msg = format_message(&tile.text);
+            // Printed data is as long as the sample, cut it the same way
+            if let Some(max_chars) = tile.text.max_chars {
+                format_msg += &match tile.text.overflow {
+                    TextOverflow::Ellipsize => format!(
+                        r#"
+    if (strlen(message) > {fitting}) strcpy(message + {max_chars}, {ellipsis});"#,
+                        fitting = max_chars + ELLIPSIS.len(),
+                        max_chars = max_chars,
+                        ellipsis = c_string(ELLIPSIS)
+                    ),
+                    _ => format!(
+                        r#"
+    message[{max_chars}] = '\0';"#,
+                        max_chars = max_chars
+                    ),
+                };
+            }
 
+            format!(
+                r#"        const int msg_size = {msg_size};
+        char message[msg_size];
 
+    {format_msg}
+    paint.DrawStringAt({x}, {y}, message, &Font{font}, COLORED);
 "#,
-                    activities = raw_elements,
-                    font = list.font_size.unwrap(),
-                    x0 = list.dim.x + 1,
-                    y0 = list.dim.y + 1,
-                    x1 = list.dim.x + list.dim.width - 1,
-                    y1 = list.dim.y + list.dim.height - 1
-                ),
-                String::default(),
+                format_msg = format_msg,
+                msg_size = message_size(&tile.text),
+                x = tile.dim.x + tile.text.dim.x,
+                y = tile.dim.y + tile.text.dim.y,
+                font = font
             )
         }
     }
 }
 
-/// Gets raw font size and samples it to sizes supported by BC display
-pub fn set_bc_font_size(tile: &mut Tile) {
-    let char_len = tile.text.name.chars().count();
+/// Method is named after the bound field, or the label of the tile
+fn method_name(text: &Text) -> String {
+    let source = match text.bindings.first() {
+        Some(binding) => binding.field,
+        None if text.name.starts_with(|c: char| c.is_ascii_alphabetic()) => text.name,
+        None => {
+            return match (text.format, text.format_kind) {
+                (Some(_), FormatKind::Strftime) => "Time",
+                (Some(_), FormatKind::Duration) => "Duration",
+                _ => "Tile",
+            }
+            .into()
+        }
+    };
 
-    tile.text.font_size = Some(8);
-    // Try to fit biggest font in the Rectangle
-    for font in [56, 42, 31, 24, 19] {
-        let char_width = get_bc_font_width(font);
-        let str_width = char_width * char_len;
+    method_name_of(source)
+}
+
+/// CamelCase of the words, "gps.in_view" is "GpsInView"
+fn method_name_of(source: &str) -> String {
+    source
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word[..1].to_uppercase() + &word[1..])
+        .collect()
+}
+
+/// Methods every view has, drawStatic and draw, without "draw"
+const VIEW_METHODS: [&str; 2] = ["Static", ""];
+
+/// Tiles showing the same field get numbered methods, "Clock", "Clock2",
+/// so do tiles named like methods of the view, "Static2"
+fn unique_methods(draws: &mut [Draw]) {
+    for idx in 0..draws.len() {
+        let taken = |name: &str| {
+            VIEW_METHODS.contains(&name) || draws[..idx].iter().any(|draw| draw.method == name)
+        };
+        if !taken(&draws[idx].method) {
+            continue;
+        }
+        let method = (2..)
+            .map(|nr| format!("{}{}", draws[idx].method, nr))
+            .find(|name| !taken(name))
+            .unwrap();
+        draws[idx].method = method;
+    }
+}
+
+/// Tiles of the same source get a single refresh, covering all of them.
+/// Tile joins the group only if the merged area does not wipe out
+/// any obstacle, nor a tile from outside of the group
+fn merge_draws(draws: Vec<Draw>, obstacles: &[Dimension]) -> Vec<Draw> {
+    let areas: Vec<_> = draws.iter().map(|draw| draw.refresh).collect();
+    // Indices of the draws in each group, and the area it refreshes
+    let mut groups: Vec<(Vec<usize>, Dimension)> = Vec::new();
+
+    for (idx, draw) in draws.iter().enumerate() {
+        let joined = groups.iter_mut().find(|(members, area)| {
+            let merged = area.union(&draw.refresh);
+            draw.source.is_some()
+                && draws[members[0]].source == draw.source
+                && obstacles.iter().all(|obstacle| !merged.overlaps(obstacle))
+                && areas.iter().enumerate().all(|(other, area)| {
+                    other == idx || members.contains(&other) || !merged.overlaps(area)
+                })
+        });
+        match joined {
+            Some((members, area)) => {
+                members.push(idx);
+                *area = area.union(&draw.refresh);
+            }
+            None => groups.push((vec![idx], draw.refresh)),
+        }
+    }
+
+    let mut draws: Vec<_> = draws.into_iter().map(Some).collect();
+    groups
+        .into_iter()
+        .map(|(members, refresh)| {
+            let mut tiles = members.iter().map(|&idx| draws[idx].take().unwrap());
+            let mut group = tiles.next().unwrap();
+            for tile in tiles {
+                group.names.extend(tile.names);
+                group.paints.extend(tile.paints);
+            }
+            if group.names.len() > 1 {
+                group.method = method_name_of(group.source.unwrap_or("Tiles"));
+            }
+            group.refresh = refresh;
+            group
+        })
+        .collect()
+}
+
+/// Refresh of the draw method, as it goes into the report
+#[derive(Debug, Clone)]
+pub struct RefreshRegion {
+    /// Name of the draw method
+    pub method: String,
+    pub tiles: usize,
+    pub area: Dimension,
+}
+
+/// Areas refreshed when all the data of the page is updated,
+/// lists refresh on their own and are not counted
+#[derive(Debug, Default, Clone)]
+pub struct RefreshReport {
+    pub regions: Vec<RefreshRegion>,
+}
+
+impl RefreshReport {
+    /// Pixels sent to the display in a single update of the page
+    pub fn pixels(&self) -> usize {
+        self.regions
+            .iter()
+            .map(|region| region.area.width * region.area.height)
+            .sum()
+    }
+}
+
+impl fmt::Display for RefreshReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} refreshes, {} pixels per update",
+            self.regions.len(),
+            self.pixels()
+        )?;
+        for region in &self.regions {
+            write!(
+                f,
+                "\n    draw{}: {} tile{}, {}x{} at ({}, {})",
+                region.method,
+                region.tiles,
+                if region.tiles == 1 { "" } else { "s" },
+                region.area.width,
+                region.area.height,
+                region.area.x,
+                region.area.y
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Code printing the text into the message buffer,
+/// formats are validated by the layout pass
+fn format_message(text: &Text) -> String {
+    let text_format = match text.format {
+        Some(text_format) => text_format,
+        None => {
+            // Label is an argument, so '%' in it is not a conversion
+            let label = match text.bindings.first() {
+                Some(binding) => format!("data.{}", binding.field),
+                None => c_string(&text.shown()),
+            };
+            return format!(r#"snprintf(message, msg_size, "%s", {});"#, label);
+        }
+    };
 
-        if str_width < tile.dim.width && font < tile.dim.height {
-            tile.text.font_size = Some(font);
-            break;
+    let pieces = format::parse(text_format, text.format_kind).unwrap_or_default();
+
+    match text.format_kind {
+        FormatKind::Printf => {
+            let mut args = vec![c_string(text_format)];
+            args.extend(data_fields(text));
+            format!("snprintf(message, msg_size, {});", args.join(", "))
+        }
+        FormatKind::Strftime => format!(
+            r#"strftime(message, msg_size, {format}, &{time});"#,
+            format = c_string(text_format),
+            time = data_fields(text).remove(0)
+        ),
+        FormatKind::Duration => {
+            let (printf, args) = format::duration_to_printf(&pieces, "seconds");
+            format!(
+                r#"const uint32_t seconds = {seconds};
+    snprintf(message, msg_size, {format}, {args});"#,
+                seconds = data_fields(text).remove(0),
+                format = c_string(&printf),
+                args = args.join(", ")
+            )
+        }
+    }
+}
+
+/// C string literal of the text, quoted and escaped. Characters past ASCII
+/// are octal escapes of their UTF-8 bytes, hex ones would eat following digits
+fn c_string(text: &str) -> String {
+    let mut literal = String::from('"');
+    for byte in text.bytes() {
+        match byte {
+            b'"' => literal.push_str("\\\""),
+            b'\\' => literal.push_str("\\\\"),
+            b'\n' => literal.push_str("\\n"),
+            b'\t' => literal.push_str("\\t"),
+            b' '..=b'~' => literal.push(byte as char),
+            _ => literal.push_str(&format!("\\{:03o}", byte)),
         }
     }
+    literal.push('"');
+    literal
 }
 
-/// Width in pixels for single character depending on the font size.
-/// Taken from the font source code.
-fn get_bc_font_width(font_size: usize) -> usize {
-    match font_size {
-        19 => 11,
-        24 => 14,
-        31 => 18,
-        42 => 24,
-        56 => 32,
-        _ => unreachable!("got font_size {}", font_size),
+/// Bytes of the message buffer, with the terminating '\0'.
+/// Formats without known longest text get the generous default
+fn message_size(text: &Text) -> usize {
+    match (text.format, text.worst_case(&|text: &str| text.len())) {
+        (None, _) if text.bindings.is_empty() => text.shown().len() + 1,
+        (_, Some(worst_case)) => worst_case.len() + 1,
+        _ => 128,
     }
 }
 
-/// Center in vertical na horizontal dimensions of the Tile
-pub fn center_text(tile: &mut Tile) {
-    let font_size = tile.text.font_size.unwrap();
-    let char_width = get_bc_font_width(font_size);
-    let char_len = tile.text.name.chars().count();
+/// Arguments passed to the format, bindings are checked by the layout pass
+fn data_fields(text: &Text) -> Vec<String> {
+    text.bindings
+        .iter()
+        .map(|binding| format!("data.{}", binding.field))
+        .collect()
+}
+
+/// Gets raw font size and samples it to sizes supported by BC display
+pub fn set_bc_font_size(tile: &mut Tile, fonts: &FontSet) {
+    tile.text.font = fitting_bc_font_size(tile, fonts);
+}
+
+/// Biggest font of the display which fits in the Tile content.
+/// If none does, it depends on the overflow: smallest font when shrinking,
+/// biggest one fitting the height when text is going to be cut
+pub fn fitting_bc_font_size(tile: &Tile, fonts: &FontSet) -> Option<usize> {
+    let content = tile.content();
+    let name = tile.text.name;
+
+    // Try to fit biggest font in the Rectangle
+    let fitting = fonts.biggest_first().find(|font| match tile.text.wrap {
+        true => {
+            let lines = font.wrap(name, content.width);
+            lines
+                .iter()
+                .all(|line| font.text_width(line) <= content.width)
+                && lines.len() * font.height <= content.height
+        }
+        false => {
+            font.text_width(&tile.text.measured(font)) < content.width
+                && font.height < content.height
+        }
+    });
+
+    let fallback = || match tile.text.overflow {
+        TextOverflow::Ellipsize | TextOverflow::Clip => fonts
+            .biggest_first()
+            .find(|font| font.height < content.height)
+            .or_else(|| fonts.smallest()),
+        TextOverflow::Shrink | TextOverflow::Fail => fonts.smallest(),
+    };
+
+    fitting.or_else(fallback).map(|font| font.size)
+}
+
+/// Places text in vertical and horizontal dimensions of the Tile,
+/// according to its alignment. Text which does not fit is
+/// handled according to the overflow of the Tile
+pub fn align_text(tile: &mut Tile, fonts: &FontSet, path: &str) -> Result<(), LayoutError> {
+    let font_size = tile
+        .text
+        .font
+        .ok_or_else(|| LayoutError::NoFont { path: path.into() })?;
+    let font = fonts
+        .get(font_size)
+        .ok_or_else(|| LayoutError::UnknownFont {
+            path: path.into(),
+            font_size,
+        })?;
+    // Text is aligned inside the padding
+    let content = tile.content();
+    let font_too_big = || LayoutError::FontTooBig {
+        path: path.into(),
+        font_size,
+        height: content.height,
+    };
+
+    let mut lines: Vec<String> = match tile.text.wrap {
+        true => font
+            .wrap(tile.text.name, content.width)
+            .into_iter()
+            .map(String::from)
+            .collect(),
+        // Formatted text is aligned for the longest one it prints
+        false => vec![tile.text.measured(font)],
+    };
+
+    let fitting_lines = content.height / font.height.max(1);
+    tile.text.overflowed = lines.len() > fitting_lines
+        || lines
+            .iter()
+            .any(|line| font.text_width(line) > content.width);
+    tile.text.max_chars = None;
+
+    if tile.text.overflowed {
+        match tile.text.overflow {
+            // Font is already the smallest one, or set explicitly
+            TextOverflow::Shrink => {}
+            TextOverflow::Fail => {
+                return Err(LayoutError::TextDoesNotFit {
+                    path: path.into(),
+                    font_size,
+                })
+            }
+            TextOverflow::Ellipsize | TextOverflow::Clip => {
+                if fitting_lines == 0 {
+                    return Err(font_too_big());
+                }
+                let suffix = match tile.text.overflow {
+                    TextOverflow::Ellipsize => ELLIPSIS,
+                    _ => "",
+                };
+                let dropped = lines.len() > fitting_lines;
+                lines.truncate(fitting_lines);
+
+                let last = lines.len() - 1;
+                for (idx, line) in lines.iter_mut().enumerate() {
+                    // Last line gets the suffix if lines following it are dropped
+                    let cut = idx == last && dropped;
+                    if !cut && font.text_width(line) <= content.width {
+                        continue;
+                    }
+                    let kept = font.fitting_chars(line, suffix, content.width);
+                    *line = line.chars().take(kept).collect::<String>() + suffix;
+                    if !tile.text.wrap {
+                        tile.text.max_chars = Some(kept);
+                    }
+                }
+            }
+        }
+    }
+
+    // Subtract height of all lines from content height to get amount of free space
+    let free_height = content
+        .height
+        .checked_sub(font.height * lines.len())
+        .ok_or_else(font_too_big)?;
+
+    // Baseline of the last line goes to the bottom
+    let last_line_top = font.height * lines.len().saturating_sub(1);
+    let y = match tile.text.v_align {
+        VAlign::Top => 0,
+        VAlign::Center => free_height / 2,
+        VAlign::Bottom => free_height,
+        VAlign::Baseline => content.height - font.ascent - last_line_top,
+    };
+
+    // Each line is aligned on its own
+    let lines: Vec<_> = lines
+        .into_iter()
+        .enumerate()
+        .map(|(idx, text)| {
+            // If str goes beyond the Tile, clamp it's width
+            let str_width = font.text_width(&text).min(content.width);
+            let free_width = content.width - str_width;
+
+            let x = match tile.text.h_align {
+                HAlign::Left => 0,
+                HAlign::Center => free_width / 2,
+                HAlign::Right => free_width,
+            };
+
+            // Text x, y relative to parent Tile (tile.dim + tile.text.dim)
+            TextLine {
+                x: content.x - tile.dim.x + x,
+                y: content.y - tile.dim.y + y + idx * font.height,
+                text,
+            }
+        })
+        .collect();
 
-    // If str goes beyond the Tile, clamp it's width
-    let str_width = (char_width * char_len).min(tile.dim.width);
+    if let Some(first) = lines.first() {
+        tile.text.dim.x = first.x;
+        tile.text.dim.y = first.y;
+    } else {
+        tile.text.dim.x = content.x - tile.dim.x;
+        tile.text.dim.y = content.y - tile.dim.y + y;
+    }
+    tile.text.lines = match tile.text.wrap {
+        true => lines,
+        false => Vec::new(),
+    };
 
-    // Text x, y relative to parent Tile (tile.dim + tile.text.dim)
-    tile.text.dim.x = (tile.dim.width - str_width) / 2;
-    // Assuming here font size describes amount of pixels,
-    // subtract from tile.dim.height to get amount of free space
-    // and div by 2 to have it vertically centered
+    Ok(())
+}
 
-    tile.text.dim.y = (tile.dim.height - font_size) / 2;
+/// C++ class of a single page, header and source of it
+#[derive(Debug)]
+pub struct BcView {
+    /// Name of the class, "StatusView"
+    pub class: String,
+    pub header: String,
+    pub source: String,
+    pub refresh: RefreshReport,
+}
+
+impl BcView {
+    /// Writes ClassName.h and ClassName.cpp, dir is relative to the crate
+    pub fn write_to_dir(&self, dir: &str) {
+        common::write_to_file(&self.header, &format!("{}/{}.h", dir, self.class));
+        common::write_to_file(&self.source, &format!("{}/{}.cpp", dir, self.class));
+    }
 }
 
 /// Generates C++ code so you don't need to type it anymore!
-pub fn render_to_bc(root: &Node, d: &Dimension) -> String {
-    let (tiles, static_elements) = render_bc_widgets(&root);
-
-    let result = format!(
-        "
-    // Following code is generated automagically,
-    // don't bother understand it.
-
-    {tiles}
-
-    void StatusView::drawStatic() {{
-        display_->enqueueStaticDraw(
-            [&](Paint &paint) {{
-            {static_elements}
-            }},
-            // Rectangle needs to cover whole widget area
-            {{0, 0, display_->getWidth(), 13}});
-    }}
-    ",
-        tiles = tiles,
-        static_elements = static_elements
+/// Page becomes a class deriving from the View, named "{page}View"
+pub fn render_to_bc(root: &Node, display: &DisplayProfile, page: &str) -> BcView {
+    let target = &display.target;
+    let class = format!("{}View", page);
+    let mut widgets = BcWidgets::default();
+    render_bc_widgets(root, &mut widgets);
+    let mut draws = merge_draws(std::mem::take(&mut widgets.draws), &widgets.obstacles);
+    unique_methods(&mut draws);
+
+    let font_groups: String = common::font_groups(root)
+        .iter()
+        .map(|(group, size)| format!("// Font group {:?}: Font{}\n", group, size))
+        .collect();
+
+    let includes: String = widgets
+        .widgets
+        .iter()
+        .map(|widget| widget.class)
+        .collect::<BTreeSet<_>>()
+        .iter()
+        .map(|class| format!("#include \"{}.h\"\n", class))
+        .collect();
+
+    let draw_declarations: String = draws
+        .iter()
+        .map(|draw| {
+            format!(
+                "    void draw{}(const {} &data);\n",
+                draw.method, target.view_data
+            )
+        })
+        .collect();
+
+    let members: String = widgets
+        .widgets
+        .iter()
+        .map(|widget| format!("    {} {};\n", widget.class, widget.member))
+        .collect();
+    let members = match members.is_empty() {
+        true => members,
+        false => format!("\nprivate:\n{}", members),
+    };
+
+    let header = format!(
+        r#"#pragma once
+
+// Following code is generated automagically,
+// don't bother understand it.
+
+#include "{base}.h"
+#include "{data}.h"
+{includes}
+class {class} : public {base} {{
+public:
+    explicit {class}({display} *display);
+
+    void drawStatic() override;
+    void draw(const {data} &data) override;
+
+{draw_declarations}{members}}};
+"#,
+        base = target.view_base,
+        data = target.view_data,
+        display = target.display,
+        includes = includes,
+        class = class,
+        draw_declarations = draw_declarations,
+        members = members,
     );
 
-    println!("Rendered:\n {}", result);
+    let initializers: String = widgets
+        .widgets
+        .iter()
+        .map(|widget| format!(",\n      {}({})", widget.member, widget.args))
+        .collect();
 
-    result
+    let draw_calls: String = draws
+        .iter()
+        .map(|draw| format!("    draw{}(data);\n", draw.method))
+        .collect();
+
+    let draw_definitions: String = draws
+        .iter()
+        .map(|draw| {
+            // Merged tiles declare their own message buffers
+            let paint = match draw.paints.len() {
+                1 => draw.paints[0].clone(),
+                _ => draw
+                    .paints
+                    .iter()
+                    .map(|paint| format!("    {{\n{}    }}\n", paint))
+                    .collect(),
+            };
+            format!(
+                r#"
+// {name}
+void {class}::draw{method}(const {data} &data) {{
+    display_->enqueueDraw(
+        [&](Paint &paint) {{
+{paint}
+        }},
+        {{{x0}, {y0}, {x1}, {y1}}});
+}}
+"#,
+                name = draw.names.join(", "),
+                class = class,
+                method = draw.method,
+                data = target.view_data,
+                paint = paint,
+                x0 = draw.refresh.x,
+                y0 = draw.refresh.y,
+                x1 = draw.refresh.x + draw.refresh.width,
+                y1 = draw.refresh.y + draw.refresh.height
+            )
+        })
+        .collect();
+
+    let draw_static = match widgets.static_area {
+        Some(area) => format!(
+            r#"    display_->enqueueStaticDraw(
+        [&](Paint &paint) {{
+{static_elements}
+        }},
+        // Rectangle covers all static elements
+        {{{x0}, {y0}, {x1}, {y1}}});
+"#,
+            static_elements = widgets.static_elements,
+            x0 = area.x,
+            y0 = area.y,
+            x1 = area.x + area.width,
+            y1 = area.y + area.height
+        ),
+        None => "    // Page has no static elements\n".into(),
+    };
+
+    let source = format!(
+        r#"// Following code is generated automagically,
+// don't bother understand it.
+// {display_name}, {width}x{height}, {color_depth}bpp
+{font_groups}
+#include "{class}.h"
+
+#include <cstdio>
+#include <cstring>
+#include <ctime>
+
+{class}::{class}({display} *display)
+    : {base}(display){initializers} {{}}
+
+void {class}::drawStatic() {{
+{draw_static}}}
+
+void {class}::draw(const {data} &data) {{
+{draw_calls}}}
+{draw_definitions}"#,
+        display_name = display.name,
+        width = display.dim.width,
+        height = display.dim.height,
+        color_depth = display.color_depth,
+        font_groups = font_groups,
+        class = class,
+        base = target.view_base,
+        data = target.view_data,
+        display = target.display,
+        initializers = initializers,
+        draw_static = draw_static,
+        draw_calls = draw_calls,
+        draw_definitions = draw_definitions,
+    );
+
+    let refresh = RefreshReport {
+        regions: draws
+            .iter()
+            .map(|draw| RefreshRegion {
+                method: draw.method.clone(),
+                tiles: draw.names.len(),
+                area: draw.refresh,
+            })
+            .collect(),
+    };
+
+    BcView {
+        class,
+        header,
+        source,
+        refresh,
+    }
 }
@@ -1,18 +1,25 @@
 use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
     fs::File,
     io::Write,
     iter::FromIterator,
     path::{Path, PathBuf},
 };
 
-use crate::bc_render::{center_text, set_bc_font_size};
+use crate::{
+    bc_render::{align_text, fitting_bc_font_size, set_bc_font_size},
+    display::DisplayProfile,
+    fonts::{Font, FontSet},
+    format::{self, Binding, CType, FormatError, FormatKind},
+};
 
 pub fn write_to_file(gui: &String, path: &str) {
     let full_path = PathBuf::from_iter([env!("CARGO_MANIFEST_DIR"), path]);
     let path = Path::new(&full_path);
 
     // Open a file in write-only mode, returns `io::Result<File>`
-    let mut file = match File::create(&path) {
+    let mut file = match File::create(path) {
         Err(why) => panic!("couldn't create {:?}: {}", path, why),
         Ok(file) => file,
     };
@@ -31,21 +38,200 @@ pub struct Dimension {
     pub height: usize,
 }
 
+impl Dimension {
+    /// Smallest rectangle covering both of them
+    pub fn union(&self, other: &Dimension) -> Dimension {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        Dimension {
+            x,
+            y,
+            width: (self.x + self.width).max(other.x + other.width) - x,
+            height: (self.y + self.height).max(other.y + other.height) - y,
+        }
+    }
+
+    /// Rectangle grown outward, so x and width are multiples of the alignment,
+    /// without going past the bounds
+    pub fn aligned(&self, alignment: usize, bounds: &Dimension) -> Dimension {
+        let alignment = alignment.max(1);
+        let x = (self.x / alignment * alignment).max(bounds.x);
+        let end = (self.x + self.width)
+            .div_ceil(alignment)
+            .saturating_mul(alignment)
+            .min(bounds.x + bounds.width);
+        Dimension {
+            x,
+            width: end.saturating_sub(x),
+            ..*self
+        }
+    }
+
+    /// True if rectangles share at least one pixel
+    pub fn overlaps(&self, other: &Dimension) -> bool {
+        self.x < other.x + other.width
+            && other.x < self.x + self.width
+            && self.y < other.y + other.height
+            && other.y < self.y + self.height
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct Text {
     pub dim: Dimension,
     pub name: &'static str,
     pub format: Option<&'static str>,
+    pub format_kind: FormatKind,
+    // Fields of the view data printed by the format, in order,
+    // a string field alone is printed without format
+    pub bindings: Vec<Binding>,
+    // Tiles of the same source are updated together, their refreshes may be merged
+    pub source: Option<&'static str>,
     // Font may be set by the user, then
     // orchestrator is not allowed to change it
     // TODO: Change to enum
     pub font_size: Option<usize>,
+    // Font the text is drawn with, picked by the layout pass:
+    // the one set by the user, inherited, shared by the group or the biggest fitting
+    pub font: Option<usize>,
+    // Tiles in the same group get the same font,
+    // the biggest one which fits all of them
+    pub font_group: Option<&'static str>,
+    pub h_align: HAlign,
+    pub v_align: VAlign,
+    // Text is broken between words into lines which fit the tile
+    pub wrap: bool,
+    // Lines of wrapped text, filled by the layout pass
+    pub lines: Vec<TextLine>,
+    // What to do with text which does not fit the tile
+    pub overflow: TextOverflow,
+    // Set by the layout pass, if text does not fit the tile in its font
+    pub overflowed: bool,
+    // Amount of leading characters drawn, if the overflow cut the text
+    pub max_chars: Option<usize>,
+}
+
+impl Text {
+    /// Text the way it is drawn, after the overflow cut it
+    pub fn shown(&self) -> String {
+        if self.wrap {
+            let lines: Vec<_> = self.lines.iter().map(|line| line.text.as_str()).collect();
+            return lines.join(" ");
+        }
+
+        match self.max_chars {
+            Some(max_chars) => {
+                let kept: String = self.name.chars().take(max_chars).collect();
+                match self.overflow {
+                    TextOverflow::Ellipsize => kept + ELLIPSIS,
+                    _ => kept,
+                }
+            }
+            None => self.name.into(),
+        }
+    }
+
+    /// Text without format or binding is a constant label,
+    /// drawn once with the static elements
+    pub fn is_dynamic(&self) -> bool {
+        self.format.is_some() || !self.bindings.is_empty()
+    }
+
+    /// Source set explicitly, or the first part of the bound field, "gps" for "gps.in_view"
+    pub fn source(&self) -> Option<&'static str> {
+        self.source.or_else(|| {
+            self.bindings
+                .first()
+                .and_then(|binding| binding.field.split('.').next())
+        })
+    }
+
+    /// Widest text the format prints for the bound values, measured by the width.
+    /// None if the format is unbound, some value has no range,
+    /// or the format cannot be sized, see format::worst_case
+    pub fn worst_case(&self, width: &dyn Fn(&str) -> usize) -> Option<String> {
+        let pieces = format::parse(self.format?, self.format_kind).ok()?;
+        format::worst_case(&pieces, self.format_kind, &self.bindings, width)
+    }
+
+    /// Text the font is picked for and aligned by, the widest one in the font,
+    /// falls back to the name
+    pub fn measured(&self, font: &Font) -> String {
+        self.worst_case(&|text: &str| font.text_width(text))
+            .unwrap_or_else(|| self.name.into())
+    }
+}
+
+/// Single line of wrapped text
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLine {
+    // Position relative to the parent Tile, same as Text dim
+    pub x: usize,
+    pub y: usize,
+    pub text: String,
+}
+
+/// Appended to the text cut by TextOverflow::Ellipsize,
+/// device fonts have ASCII only
+pub const ELLIPSIS: &str = "...";
+
+/// What happens with text, which does not fit the tile
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum TextOverflow {
+    /// Use the smallest font, if it still does not fit, draw beyond the tile
+    #[default]
+    Shrink,
+    /// Keep the biggest font fitting the height, cut the text and end it with "..."
+    Ellipsize,
+    /// Keep the biggest font fitting the height, cut the text
+    Clip,
+    /// Fail the layout
+    Fail,
+}
+
+/// Where text goes horizontally inside the tile content
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum HAlign {
+    Left,
+    #[default]
+    Center,
+    /// Handy for numeric columns
+    Right,
+}
+
+/// Where text goes vertically inside the tile content
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum VAlign {
+    Top,
+    #[default]
+    Center,
+    Bottom,
+    /// Baseline of the text lies on the bottom of the content,
+    /// so texts of different sizes in a row line up
+    Baseline,
 }
 
 #[derive(Debug, Default)]
 pub struct Tile {
     pub dim: Dimension,
     pub text: Text,
+    pub attrs: Attributes,
+    // Padding set by the user or the display default, chosen by the layout pass
+    pub padding: Edges,
+    // Area refreshed when the tile is redrawn, content aligned for the display
+    pub refresh: Dimension,
+}
+
+impl Tile {
+    /// Area for the text, tile shrunk by the padding
+    pub fn content(&self) -> Dimension {
+        content(&self.dim, &self.padding)
+    }
+}
+
+/// Area inside the padding, empty if the padding does not fit
+fn content(dim: &Dimension, padding: &Edges) -> Dimension {
+    padding.shrink(dim).unwrap_or_default()
 }
 
 #[derive(Debug, Default)]
@@ -54,34 +240,216 @@ pub struct List {
     pub dim: Dimension,
     // Size all elements share
     pub font_size: Option<usize>,
+    // Font of the elements, set or inherited, chosen by the layout pass
+    pub font: Option<usize>,
     // Elements which user is able to select
     pub elements: Vec<Tile>,
     // If visible_elements is less than # of elements in total
     // scroll the view
     pub visible_elements: usize,
+    // Index of the element drawn highlighted, view is scrolled to it
+    pub selected: Option<usize>,
+    pub highlight: Highlight,
+    pub attrs: Attributes,
+    // Padding set by the user or the display default, chosen by the layout pass
+    pub padding: Edges,
+    // Area refreshed when the list is redrawn, content aligned for the display
+    pub refresh: Dimension,
+}
+
+/// How list shows the selected element
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Highlight {
+    /// White text on black background
+    #[default]
+    Inverted,
+    /// Black frame around the element
+    Frame,
 }
 #[derive(Debug)]
 pub struct HardSplit {
+    // Dimension of whole split
+    pub dim: Dimension,
     pub first: Box<Node>,
     pub first_occupation_percent: f64,
     pub second: Box<Node>,
-    pub second_occupation_percent: f64,
+    // Font inherited by children which do not set their own
+    pub font_size: Option<usize>,
+    pub attrs: Attributes,
+}
+
+/// Children of V or H layout
+#[derive(Debug, Default)]
+pub struct Layout {
+    // Dimension of whole layout
+    pub dim: Dimension,
+    pub nodes: Vec<Node>,
+    // Font inherited by children which do not set their own
+    pub font_size: Option<usize>,
+    pub attrs: Attributes,
+}
+
+/// How much space node takes in the parent V or H layout,
+/// along one axis
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Size {
+    /// Relative share of the space left by Pixels and Percent siblings
+    Weight(usize),
+    /// Exact amount of pixels
+    Pixels(usize),
+    /// Part of the parent area, 0.0 - 1.0, same as in h_split
+    Percent(f64),
+}
+
+impl Default for Size {
+    fn default() -> Self {
+        Size::Weight(1)
+    }
+}
+
+impl Size {
+    /// Size across the layout direction, Weight fills whole extent
+    fn cross(&self, extent: usize) -> usize {
+        match *self {
+            Size::Weight(_) => extent,
+            Size::Pixels(pixels) => pixels.min(extent),
+            Size::Percent(percent) => ((extent as f64 * percent) as usize).min(extent),
+        }
+    }
+}
+
+/// Attributes common for nodes which occupy space in the layout
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Attributes {
+    pub width: Size,
+    pub height: Size,
+    // Space around the node, taken from the area parent gives to it.
+    // If not set, display default from LayoutConfig is used
+    pub margin: Option<Edges>,
+    // Space between the node border and its content
+    pub padding: Option<Edges>,
+}
+
+/// Amount of pixels on each side of the rectangle
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Edges {
+    pub top: usize,
+    pub right: usize,
+    pub bottom: usize,
+    pub left: usize,
+}
+
+impl Edges {
+    pub const fn all(pixels: usize) -> Self {
+        Edges {
+            top: pixels,
+            right: pixels,
+            bottom: pixels,
+            left: pixels,
+        }
+    }
+
+    pub const fn symmetric(vertical: usize, horizontal: usize) -> Self {
+        Edges {
+            top: vertical,
+            right: horizontal,
+            bottom: vertical,
+            left: horizontal,
+        }
+    }
+
+    fn without_vertical(&self) -> Self {
+        Edges {
+            top: 0,
+            bottom: 0,
+            ..*self
+        }
+    }
+
+    fn without_horizontal(&self) -> Self {
+        Edges {
+            left: 0,
+            right: 0,
+            ..*self
+        }
+    }
+
+    /// Rectangle left after cutting edges off, None if edges do not fit
+    pub fn shrink(&self, d: &Dimension) -> Option<Dimension> {
+        Some(Dimension {
+            x: d.x + self.left,
+            y: d.y + self.top,
+            width: d.width.checked_sub(self.left + self.right)?,
+            height: d.height.checked_sub(self.top + self.bottom)?,
+        })
+    }
+}
+
+/// Display specific defaults of the layout pass, used for nodes
+/// which do not set margin or padding on their own
+#[derive(Debug, Clone)]
+pub struct LayoutConfig {
+    /// Margin around tiles, lists and layouts
+    pub margin: Edges,
+    /// Padding inside tiles and lists, refreshed area is shrunk by it,
+    /// so static elements around are not wiped out
+    pub padding: Edges,
+    /// Padding inside V, H and splits
+    pub layout_padding: Edges,
+    pub h_line_margin: Edges,
+    pub v_line_margin: Edges,
+    /// Fonts available on the display
+    pub fonts: FontSet,
+    /// Characters missing in the font fail the layout,
+    /// instead of being only reported
+    pub strict_glyphs: bool,
+    /// Refreshed areas grow to x and width being multiples of it,
+    /// like 8 for controllers of 1bpp displays, which update whole bytes
+    pub refresh_alignment: usize,
+}
+
+/// Layout of the Sharp Memory LCD 2.7"
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        LayoutConfig {
+            margin: Edges::default(),
+            padding: Edges::all(1),
+            layout_padding: Edges::default(),
+            h_line_margin: Edges::symmetric(0, 13),
+            v_line_margin: Edges::symmetric(3, 0),
+            fonts: FontSet::sharp_mip(),
+            strict_glyphs: false,
+            // Memory LCD updates whole lines
+            refresh_alignment: 1,
+        }
+    }
+}
+
+/// Horizontal or vertical line, drawn on top of other widgets
+#[derive(Debug, Default)]
+pub struct Line {
+    pub dim: Dimension,
+    // Space cut off the line ends, if not set LayoutConfig is used
+    pub margin: Option<Edges>,
 }
 
 #[derive(Debug)]
 pub enum Node {
-    /// Vertical layout, splits area evenly among elements in vector
-    V(Vec<Box<Node>>),
+    /// Vertical layout, splits area among elements according to their height
+    V(Layout),
 
-    /// Horizontal layout, splits area evenly among elements in vector
-    H(Vec<Box<Node>>),
+    /// Horizontal layout, splits area among elements according to their width
+    H(Layout),
 
     // Hard Horizontal split, splits into two parts, uneven
     HH(HardSplit),
 
+    // Hard Vertical split, splits into two parts side by side, uneven
+    VV(HardSplit),
+
     Tile(Tile),
-    HorizontalLine(Dimension),
-    VerticalLine(Dimension),
+    HorizontalLine(Line),
+    VerticalLine(Line),
     VListWidget(List),
 }
 /// [] [] []
@@ -89,7 +457,10 @@ pub fn h_layout<T>(elements: T) -> Node
 where
     T: IntoIterator<Item = Node>,
 {
-    Node::H(elements.into_iter().map(|e| Box::new(e)).collect())
+    Node::H(Layout {
+        nodes: elements.into_iter().collect(),
+        ..Default::default()
+    })
 }
 
 /// []
@@ -105,24 +476,65 @@ where
 {
     // Here real magic begins, because possibly stack allocated element
     // becomes heap allocated
-    Node::V(elements.into_iter().map(|e| Box::new(e)).collect())
+    Node::V(Layout {
+        nodes: elements.into_iter().collect(),
+        ..Default::default()
+    })
 }
 
 pub fn h_split(left: Node, left_percent: f64, right: Node) -> Node {
     Node::HH(HardSplit {
+        dim: Dimension::default(),
+        first: Box::new(left),
+        first_occupation_percent: left_percent,
+        second: Box::new(right),
+        font_size: None,
+        attrs: Attributes::default(),
+    })
+}
+
+/// [left][  right  ]
+pub fn v_split(left: Node, left_percent: f64, right: Node) -> Node {
+    Node::VV(HardSplit {
+        dim: Dimension::default(),
         first: Box::new(left),
         first_occupation_percent: left_percent,
         second: Box::new(right),
-        second_occupation_percent: 1.0 - left_percent,
+        font_size: None,
+        attrs: Attributes::default(),
     })
 }
 
+/// Splits area top to bottom, each part takes given percent of the height
+pub fn h_split_n<T>(parts: T) -> Node
+where
+    T: IntoIterator<Item = (Node, f64)>,
+{
+    v_layout(
+        parts
+            .into_iter()
+            .map(|(node, percent)| node.with_height(Size::Percent(percent))),
+    )
+}
+
+/// Splits area left to right, each part takes given percent of the width
+pub fn v_split_n<T>(parts: T) -> Node
+where
+    T: IntoIterator<Item = (Node, f64)>,
+{
+    h_layout(
+        parts
+            .into_iter()
+            .map(|(node, percent)| node.with_width(Size::Percent(percent))),
+    )
+}
+
 pub fn h_line() -> Node {
-    Node::HorizontalLine(Dimension::default())
+    Node::HorizontalLine(Line::default())
 }
 
 pub fn v_line() -> Node {
-    Node::VerticalLine(Dimension::default())
+    Node::VerticalLine(Line::default())
 }
 
 pub fn tile(name: &'static str) -> Node {
@@ -158,6 +570,21 @@ where
     })
 }
 
+impl List {
+    /// Elements on the page with the selected element
+    pub fn visible_range(&self) -> std::ops::Range<usize> {
+        let page = self.selected.unwrap_or_default() / self.visible_elements.max(1);
+        let start = page * self.visible_elements;
+
+        start..(start + self.visible_elements).min(self.elements.len())
+    }
+
+    /// Area for the elements, list shrunk by the padding
+    pub fn content(&self) -> Dimension {
+        content(&self.dim, &self.padding)
+    }
+}
+
 impl Node {
     pub fn with_format(mut self, format: &'static str) -> Self {
         // TODO: any better way to do this?
@@ -170,8 +597,154 @@ impl Node {
         }
     }
 
+    /// Formats wall clock time, using strftime conversions
+    pub fn with_strftime(self, format: &'static str) -> Self {
+        self.with_format_kind(format, FormatKind::Strftime)
+    }
+
+    /// Formats elapsed seconds, like "%T" for 01:12:20
+    pub fn with_duration(self, format: &'static str) -> Self {
+        self.with_format_kind(format, FormatKind::Duration)
+    }
+
+    fn with_format_kind(mut self, format: &'static str, kind: FormatKind) -> Self {
+        match self {
+            Node::Tile(ref mut tile) => {
+                tile.text.format = Some(format);
+                tile.text.format_kind = kind;
+                self
+            }
+            _ => panic!("Cannot set format on {:?}", self),
+        }
+    }
+
+    /// Binds next value of the format to the field of the view data,
+    /// like `.bind("gps.in_view", CType::U8)`
+    pub fn bind(mut self, field: &'static str, ctype: CType) -> Self {
+        match self {
+            Node::Tile(ref mut tile) => {
+                tile.text.bindings.push(Binding {
+                    field,
+                    ctype,
+                    range: None,
+                });
+                self
+            }
+            _ => panic!("Cannot set binding on {:?}", self),
+        }
+    }
+
+    /// Declares values the last bound field takes, the tile is laid out
+    /// for the longest text they print, like "-40.00" for `%5.2f` from -40 to 85
+    pub fn with_range(mut self, min: f64, max: f64) -> Self {
+        match self {
+            Node::Tile(ref mut tile) if !tile.text.bindings.is_empty() => {
+                let last = tile.text.bindings.len() - 1;
+                tile.text.bindings[last].range = Some((min, max));
+                self
+            }
+            _ => panic!("Cannot set range on {:?}", self),
+        }
+    }
+
+    /// Label changes at runtime, it shows the string field of the view data,
+    /// like `.dynamic("activity")`. Text of the tile is the sample it is laid out for
+    pub fn dynamic(self, field: &'static str) -> Self {
+        self.bind(field, CType::Str)
+    }
+
+    /// Names the data source, or update rate, the tile is redrawn with
+    pub fn with_source(mut self, source: &'static str) -> Self {
+        match self {
+            Node::Tile(ref mut tile) => {
+                tile.text.source = Some(source);
+                self
+            }
+            _ => panic!("Cannot set source on {:?}", self),
+        }
+    }
+
+    /// Sets where text goes inside the tile
+    pub fn with_align(mut self, h_align: HAlign, v_align: VAlign) -> Self {
+        match self {
+            Node::Tile(ref mut tile) => {
+                tile.text.h_align = h_align;
+                tile.text.v_align = v_align;
+                self
+            }
+            _ => panic!("Cannot set align on {:?}", self),
+        }
+    }
+
+    /// Puts the tile in the font group, all tiles in the group which
+    /// do not set the font explicitly share the biggest font fitting each of them
+    pub fn font_group(mut self, group: &'static str) -> Self {
+        match self {
+            Node::Tile(ref mut tile) => {
+                tile.text.font_group = Some(group);
+                self
+            }
+            _ => panic!("Cannot set font_group on {:?}", self),
+        }
+    }
+
+    /// Breaks text of the tile into lines, instead of clamping it.
+    /// Font picked by the layout is the biggest one, which fits whole paragraph
+    pub fn wrapped(mut self) -> Self {
+        match self {
+            Node::Tile(ref mut tile) => {
+                tile.text.wrap = true;
+                self
+            }
+            _ => panic!("Cannot wrap {:?}", self),
+        }
+    }
+
+    /// Sets how many elements list shows at once, the rest is scrolled
+    pub fn with_visible(mut self, visible_elements: usize) -> Self {
+        match self {
+            Node::VListWidget(ref mut list) => {
+                list.visible_elements = visible_elements;
+                self
+            }
+            _ => panic!("Cannot set visible elements on {:?}", self),
+        }
+    }
+
+    /// Sets which list element is selected, and how it is highlighted
+    pub fn with_selected(mut self, selected: usize, highlight: Highlight) -> Self {
+        match self {
+            Node::VListWidget(ref mut list) => {
+                list.selected = Some(selected);
+                list.highlight = highlight;
+                self
+            }
+            _ => panic!("Cannot select element of {:?}", self),
+        }
+    }
+
+    /// Sets what happens with text which does not fit the tile,
+    /// on VListWidget it applies to all elements
+    pub fn with_overflow(mut self, overflow: TextOverflow) -> Self {
+        match self {
+            Node::Tile(ref mut tile) => {
+                tile.text.overflow = overflow;
+                self
+            }
+            Node::VListWidget(ref mut list) => {
+                for element in list.elements.iter_mut() {
+                    element.text.overflow = overflow;
+                }
+                self
+            }
+            _ => panic!("Cannot set overflow on {:?}", self),
+        }
+    }
+
     /// Sets explicitly font size. Make sure it will fit in the
-    /// Rectangle height, otherwise dim validation will panic
+    /// Rectangle height, otherwise dim validation fails.
+    /// On V, H, HH and VV font is inherited by all children,
+    /// unless they set their own
     pub fn with_font_size(mut self, size: usize) -> Self {
         match self {
             Node::Tile(ref mut tile) => {
@@ -181,136 +754,613 @@ impl Node {
             Node::VListWidget(ref mut list) => {
                 list.font_size = Some(size);
 
-                let _: Vec<_> = list
-                    .elements
-                    .iter_mut()
-                    .map(|element| element.text.font_size = Some(size))
-                    .collect();
+                for element in list.elements.iter_mut() {
+                    element.text.font_size = Some(size);
+                }
+                self
+            }
+            Node::V(ref mut layout) | Node::H(ref mut layout) => {
+                layout.font_size = Some(size);
+                self
+            }
+            Node::HH(ref mut split) | Node::VV(ref mut split) => {
+                split.font_size = Some(size);
                 self
             }
-            // TODO: Node::V -> apply font size for all children
-
             _ => panic!("Cannot set font_size on {:?}", self),
         }
     }
+
+    /// Sets width the node takes in the parent H layout,
+    /// or in the parent V layout, if it should not fill it whole
+    pub fn with_width(mut self, width: Size) -> Self {
+        match self.attrs_mut() {
+            Some(attrs) => {
+                attrs.width = width;
+                self
+            }
+            None => panic!("Cannot set width on {:?}", self),
+        }
+    }
+
+    /// Sets height the node takes in the parent V layout,
+    /// or in the parent H layout, if it should not fill it whole
+    pub fn with_height(mut self, height: Size) -> Self {
+        match self.attrs_mut() {
+            Some(attrs) => {
+                attrs.height = height;
+                self
+            }
+            None => panic!("Cannot set height on {:?}", self),
+        }
+    }
+
+    /// Sets space around the node, for lines it shortens the line
+    pub fn with_margin(mut self, margin: Edges) -> Self {
+        match self {
+            Node::HorizontalLine(ref mut line) | Node::VerticalLine(ref mut line) => {
+                line.margin = Some(margin);
+            }
+            _ => self.attrs_mut().unwrap().margin = Some(margin),
+        }
+        self
+    }
+
+    /// Sets space between node border and its content,
+    /// for tiles and lists it also shrinks the refreshed area
+    pub fn with_padding(mut self, padding: Edges) -> Self {
+        match self.attrs_mut() {
+            Some(attrs) => {
+                attrs.padding = Some(padding);
+                self
+            }
+            None => panic!("Cannot set padding on {:?}", self),
+        }
+    }
+
+    /// Puts a separator between every two neighbours of the layout,
+    /// h_line between rows of V, v_line between columns of H
+    pub fn separated(self) -> Self {
+        match self {
+            Node::V(layout) => Node::V(separate(layout, h_line)),
+            Node::H(layout) => Node::H(separate(layout, v_line)),
+            _ => panic!("Cannot separate {:?}", self),
+        }
+    }
+
+    /// Area taken by the node, valid after invalidate_dimensions
+    pub fn dim(&self) -> Dimension {
+        match self {
+            Node::V(layout) | Node::H(layout) => layout.dim,
+            Node::HH(split) | Node::VV(split) => split.dim,
+            Node::Tile(tile) => tile.dim,
+            Node::HorizontalLine(line) | Node::VerticalLine(line) => line.dim,
+            Node::VListWidget(list) => list.dim,
+        }
+    }
+
+    /// Area given to the node by its parent, margin included
+    fn slot(&self, config: &LayoutConfig) -> Dimension {
+        let d = self.dim();
+        let margin = match self.attrs() {
+            Some(attrs) => attrs.margin.unwrap_or(config.margin),
+            None => Edges::default(),
+        };
+        Dimension {
+            x: d.x - margin.left,
+            y: d.y - margin.top,
+            width: d.width + margin.left + margin.right,
+            height: d.height + margin.top + margin.bottom,
+        }
+    }
+
+    /// Where the node is divided for the first time, in given direction:
+    /// x of the boundary between columns, or y between rows.
+    /// That's where a line across the parent layout should go.
+    fn boundary(&self, direction: Direction, config: &LayoutConfig) -> Option<usize> {
+        let second =
+            match (direction, self) {
+                (Direction::Horizontal, Node::H(layout))
+                | (Direction::Vertical, Node::V(layout)) => layout
+                    .nodes
+                    .iter()
+                    .filter(|node| node.attrs().is_some())
+                    .nth(1)?,
+                (Direction::Horizontal, Node::VV(split))
+                | (Direction::Vertical, Node::HH(split)) => &split.second,
+                _ => return None,
+            };
+        match direction {
+            Direction::Horizontal => Some(second.slot(config).x),
+            Direction::Vertical => Some(second.slot(config).y),
+        }
+    }
+
+    /// Name of the node used in error paths
+    fn label(&self) -> String {
+        match self {
+            Node::V(_) => "V".into(),
+            Node::H(_) => "H".into(),
+            Node::HH(_) => "HH".into(),
+            Node::VV(_) => "VV".into(),
+            Node::Tile(tile) => format!("Tile({:?})", tile.text.name),
+            Node::HorizontalLine(_) => "HorizontalLine".into(),
+            Node::VerticalLine(_) => "VerticalLine".into(),
+            Node::VListWidget(_) => "VListWidget".into(),
+        }
+    }
+
+    fn attrs(&self) -> Option<&Attributes> {
+        match self {
+            Node::V(layout) | Node::H(layout) => Some(&layout.attrs),
+            Node::HH(split) | Node::VV(split) => Some(&split.attrs),
+            Node::Tile(tile) => Some(&tile.attrs),
+            Node::VListWidget(list) => Some(&list.attrs),
+            Node::HorizontalLine(_) | Node::VerticalLine(_) => None,
+        }
+    }
+
+    fn attrs_mut(&mut self) -> Option<&mut Attributes> {
+        match self {
+            Node::V(layout) | Node::H(layout) => Some(&mut layout.attrs),
+            Node::HH(split) | Node::VV(split) => Some(&mut split.attrs),
+            Node::Tile(tile) => Some(&mut tile.attrs),
+            Node::VListWidget(list) => Some(&mut list.attrs),
+            Node::HorizontalLine(_) | Node::VerticalLine(_) => None,
+        }
+    }
+}
+
+/// Puts separator between every two neighbouring widgets,
+/// unless there is a line between them already
+fn separate(layout: Layout, separator: fn() -> Node) -> Layout {
+    let mut nodes: Vec<Node> = Vec::with_capacity(layout.nodes.len() * 2);
+
+    for node in layout.nodes {
+        let after_widget = nodes.last().is_some_and(|last| last.attrs().is_some());
+        if after_widget && node.attrs().is_some() {
+            nodes.push(separator());
+        }
+        nodes.push(node);
+    }
+
+    Layout { nodes, ..layout }
+}
+
+/// Splits extent among sizes: first Pixels and Percent sizes
+/// take what they need, what is left goes to Weights, proportionally.
+///
+/// Rounding down leaves few pixels unused, they are given away one by one
+/// starting from the first flexible node, so children tile the parent exactly.
+/// Flexible are Weights, or Percents if there are no Weights and they add up to 1.0
+fn distribute(extent: usize, sizes: &[Size], path: &str) -> Result<Vec<usize>, LayoutError> {
+    let fixed: usize = sizes
+        .iter()
+        .map(|size| match *size {
+            Size::Weight(_) => 0,
+            Size::Pixels(pixels) => pixels,
+            Size::Percent(percent) => (extent as f64 * percent) as usize,
+        })
+        .sum();
+
+    let weights: usize = sizes
+        .iter()
+        .map(|size| match *size {
+            Size::Weight(weight) => weight,
+            _ => 0,
+        })
+        .sum();
+
+    let percents: f64 = sizes
+        .iter()
+        .map(|size| match *size {
+            Size::Percent(percent) => percent,
+            _ => 0.0,
+        })
+        .sum();
+
+    let left = extent
+        .checked_sub(fixed)
+        .ok_or_else(|| LayoutError::Overflow {
+            path: path.into(),
+            needed: fixed,
+            available: extent,
+        })?;
+
+    let weighted = sizes.iter().any(|size| matches!(size, Size::Weight(_)));
+    if weighted && weights == 0 {
+        return Err(LayoutError::ZeroWeights { path: path.into() });
+    }
+
+    let mut distributed: Vec<_> = sizes
+        .iter()
+        .map(|size| match *size {
+            Size::Weight(weight) => left * weight / weights,
+            Size::Pixels(pixels) => pixels,
+            Size::Percent(percent) => (extent as f64 * percent) as usize,
+        })
+        .collect();
+
+    let flexible: Vec<_> = sizes
+        .iter()
+        .enumerate()
+        .filter(|(_, size)| match *size {
+            Size::Weight(weight) => *weight > 0,
+            Size::Percent(_) => weights == 0 && (percents - 1.0).abs() < 1e-6,
+            Size::Pixels(_) => false,
+        })
+        .map(|(idx, _)| idx)
+        .collect();
+
+    let leftover = extent.saturating_sub(distributed.iter().sum());
+
+    for &idx in flexible.iter().cycle().take(leftover) {
+        distributed[idx] += 1;
+    }
+
+    Ok(distributed)
+}
+
+/// Direction in which layout places its children
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Direction {
+    /// Top to bottom, V
+    Vertical,
+    /// Left to right, H
+    Horizontal,
+}
+
+/// Reason the layout pass gave up, path points to the offending node,
+/// e.g. root/HH.second/V[2]/Tile("Paused")
+#[derive(Debug, PartialEq)]
+pub enum LayoutError {
+    /// Layout has nothing to split the area among, or list has no elements
+    Empty { path: String },
+    /// Children with Pixels and Percent sizes need more than parent has
+    Overflow {
+        path: String,
+        needed: usize,
+        available: usize,
+    },
+    /// Children sized by Weight have nothing but Weight(0),
+    /// so there is no share of the space left for them
+    ZeroWeights { path: String },
+    /// Area is smaller than margins of the node
+    TooSmall {
+        path: String,
+        needed: usize,
+        available: usize,
+    },
+    /// Font is higher than the tile
+    FontTooBig {
+        path: String,
+        font_size: usize,
+        height: usize,
+    },
+    /// There is no such font on the display
+    UnknownFont { path: String, font_size: usize },
+    /// Font size is neither set nor calculated
+    NoFont { path: String },
+    /// Line across the layout is not followed by anything it could split
+    NothingToSeparate { path: String },
+    /// Selected element is past the end of the list
+    NoSuchElement {
+        path: String,
+        selected: usize,
+        elements: usize,
+    },
+    /// Format of the tile has conversions, which are not valid for its kind
+    BadFormat { path: String, error: FormatError },
+    /// Text does not fit the tile and its TextOverflow is Fail
+    TextDoesNotFit { path: String, font_size: usize },
+    /// Wrapped text is drawn line by line as it was laid out,
+    /// so it cannot be formatted nor bound
+    WrappedDynamic { path: String },
+    /// Font has no glyphs for some characters of the text,
+    /// reported as an error only if LayoutConfig asks for it
+    MissingGlyphs(MissingGlyphs),
+    /// Refreshing the tile or list would wipe out a line or label drawn once
+    /// by drawStatic, or the text of another tile
+    RefreshOverlaps { path: String, element: String },
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayoutError::Empty { path } => write!(f, "{}: nothing to lay out", path),
+            LayoutError::Overflow {
+                path,
+                needed,
+                available,
+            } => write!(
+                f,
+                "{}: children need {}px, but there is only {}px",
+                path, needed, available
+            ),
+            LayoutError::ZeroWeights { path } => {
+                write!(f, "{}: weights of the children add up to 0", path)
+            }
+            LayoutError::TooSmall {
+                path,
+                needed,
+                available,
+            } => write!(
+                f,
+                "{}: needs at least {}px, but there is only {}px",
+                path, needed, available
+            ),
+            LayoutError::FontTooBig {
+                path,
+                font_size,
+                height,
+            } => write!(
+                f,
+                "{}: font {} does not fit in the height of {}px",
+                path, font_size, height
+            ),
+            LayoutError::UnknownFont { path, font_size } => {
+                write!(f, "{}: there is no font of size {}", path, font_size)
+            }
+            LayoutError::NoFont { path } => write!(f, "{}: font size is not set", path),
+            LayoutError::NothingToSeparate { path } => {
+                write!(f, "{}: line is not followed by a split widget", path)
+            }
+            LayoutError::NoSuchElement {
+                path,
+                selected,
+                elements,
+            } => write!(
+                f,
+                "{}: cannot select element {} out of {}",
+                path, selected, elements
+            ),
+            LayoutError::BadFormat { path, error } => write!(f, "{}: {}", path, error),
+            LayoutError::TextDoesNotFit { path, font_size } => {
+                write!(f, "{}: text does not fit in font {}", path, font_size)
+            }
+            LayoutError::WrappedDynamic { path } => {
+                write!(f, "{}: wrapped text cannot change at runtime", path)
+            }
+            LayoutError::MissingGlyphs(missing) => missing.fmt(f),
+            LayoutError::RefreshOverlaps { path, element } => {
+                write!(f, "{}: refreshed area overlaps {}", path, element)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LayoutError {}
+
+/// Characters of the node text, which font cannot draw
+#[derive(Debug, Clone, PartialEq)]
+pub struct MissingGlyphs {
+    pub path: String,
+    pub font_size: usize,
+    pub chars: Vec<char>,
+}
+
+impl fmt::Display for MissingGlyphs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let chars: Vec<_> = self
+            .chars
+            .iter()
+            .map(|&c| format!("U+{:04X} {:?}", c as u32, c))
+            .collect();
+        write!(
+            f,
+            "{}: font {} has no glyphs for {}",
+            self.path,
+            self.font_size,
+            chars.join(", ")
+        )
+    }
+}
+
+/// Text which does not fit the tile, and what was done with it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Overflowed {
+    pub path: String,
+    pub font_size: usize,
+    pub overflow: TextOverflow,
+    /// Text drawn on the display
+    pub shown: String,
+}
+
+impl fmt::Display for Overflowed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.overflow {
+            TextOverflow::Ellipsize => write!(
+                f,
+                "{}: text ellipsized to {:?} in font {}",
+                self.path, self.shown, self.font_size
+            ),
+            TextOverflow::Clip => write!(
+                f,
+                "{}: text clipped to {:?} in font {}",
+                self.path, self.shown, self.font_size
+            ),
+            TextOverflow::Shrink | TextOverflow::Fail => write!(
+                f,
+                "{}: text goes beyond the tile even in font {}",
+                self.path, self.font_size
+            ),
+        }
+    }
+}
+
+/// Findings of the layout pass, which do not stop it
+#[derive(Debug, Default, PartialEq)]
+pub struct LayoutReport {
+    pub missing_glyphs: Vec<MissingGlyphs>,
+    pub overflowed: Vec<Overflowed>,
 }
 
 /// Gets root of the gui, and updates leaf dimensions with
-/// correct x, y, width, height values
-pub fn invalidate_dimensions(root: &mut Node, d: &Dimension) {
+/// correct x, y, width, height values, for the whole area of the display
+pub fn invalidate_dimensions(
+    root: &mut Node,
+    display: &DisplayProfile,
+) -> Result<LayoutReport, LayoutError> {
+    let d = &display.dim;
+    let config = &display.layout;
+    let path = root_path(root);
+    invalidate_node(root, d, config, &path, None)?;
+    resolve_font_groups(root, &config.fonts, &path)?;
+
+    let mut report = LayoutReport::default();
+    visit_tiles_mut(root, &path, &mut |tile, path| {
+        if tile.text.wrap && tile.text.is_dynamic() {
+            return Err(LayoutError::WrappedDynamic { path: path.into() });
+        }
+        let text_format = match (tile.text.format, tile.text.bindings.is_empty()) {
+            (Some(text_format), _) => Some((text_format, tile.text.format_kind)),
+            // Bound label prints the field as it is
+            (None, false) => Some(("%s", FormatKind::Printf)),
+            (None, true) => None,
+        };
+        if let Some((text_format, kind)) = text_format {
+            let bad_format = |error| LayoutError::BadFormat {
+                path: path.into(),
+                error,
+            };
+            let pieces = format::parse(text_format, kind).map_err(bad_format)?;
+            format::check_bindings(&pieces, kind, &tile.text.bindings).map_err(bad_format)?;
+        }
+        if let Some(missing) = missing_glyphs(tile, &config.fonts, path) {
+            if config.strict_glyphs {
+                return Err(LayoutError::MissingGlyphs(missing));
+            }
+            report.missing_glyphs.push(missing);
+        }
+        if let (true, Some(font_size)) = (tile.text.overflowed, tile.text.font) {
+            report.overflowed.push(Overflowed {
+                path: path.into(),
+                font_size,
+                overflow: tile.text.overflow,
+                shown: tile.text.shown(),
+            });
+        }
+        Ok(())
+    })?;
+
+    align_refresh_areas(root, &path, config.refresh_alignment, d);
+    check_refresh_areas(root, &path)?;
+
+    Ok(report)
+}
+
+/// Checks text and literal part of the format against glyphs of the tile font
+fn missing_glyphs(tile: &Tile, fonts: &FontSet, path: &str) -> Option<MissingGlyphs> {
+    let font_size = tile.text.font?;
+    let font = fonts.get(font_size)?;
+    let literals = tile
+        .text
+        .format
+        .and_then(|text_format| format::parse(text_format, tile.text.format_kind).ok())
+        .map(|pieces| format::literals(&pieces))
+        .unwrap_or_default();
+
+    let chars: BTreeSet<_> = tile
+        .text
+        .name
+        .chars()
+        .chain(literals.chars())
+        .filter(|c| !font.glyphs.contains(c))
+        .collect();
+
+    if chars.is_empty() {
+        return None;
+    }
+
+    Some(MissingGlyphs {
+        path: path.into(),
+        font_size,
+        chars: chars.into_iter().collect(),
+    })
+}
+
+/// Cuts edges off the area, or tells how much is missing
+fn shrink(d: &Dimension, edges: &Edges, path: &str) -> Result<Dimension, LayoutError> {
+    edges.shrink(d).ok_or_else(|| {
+        let (needed, available) = if d.width < edges.left + edges.right {
+            (edges.left + edges.right, d.width)
+        } else {
+            (edges.top + edges.bottom, d.height)
+        };
+        LayoutError::TooSmall {
+            path: path.into(),
+            needed,
+            available,
+        }
+    })
+}
+
+/// font_size is inherited from the closest parent which sets it.
+/// Settings of the nodes are left as they are, so the same tree
+/// may be laid out again, for another display
+fn invalidate_node(
+    root: &mut Node,
+    d: &Dimension,
+    config: &LayoutConfig,
+    path: &str,
+    font_size: Option<usize>,
+) -> Result<(), LayoutError> {
+    // Area given by the parent includes the margin,
+    // missing margins and paddings are taken from display defaults
+    let d = &match root.attrs_mut() {
+        Some(attrs) => {
+            let margin = attrs.margin.unwrap_or(config.margin);
+            shrink(d, &margin, path)?
+        }
+        None => *d,
+    };
+
     match root {
-        Node::V(nodes) => {
-            // Get number of elements which are NOT {h,v}_lines
-            let len = nodes
-                .iter()
-                .filter(|e| match e.as_ref() {
-                    Node::HorizontalLine(_) | Node::VerticalLine(_) => false,
-                    _ => true,
-                })
-                .count();
-
-            // Split area evenly by nodes which are NOT {h,v}_lines
-            let height = ((d.height / len) as f64).round() as usize;
-
-            let mut h_lines_count = 0;
-
-            let _: Vec<_> = nodes
-                .iter_mut()
-                .enumerate()
-                .map(|(idx, node)| {
-                    // H or V lines overlap on other widgets,
-                    // so each time h_line is present we substract
-                    // the idx, so widget goes to the place under
-                    // the h_line
-                    // Without correction:
-                    // [widget1]
-                    // [h_line]
-                    // [empty_space]
-                    // [widget2]
-                    // [widget3] - outside the screen!
-
-                    // With correction:
-                    // [widget1]
-                    // [h_line]
-                    // [widget2]
-                    // [widget3] - bottom of the screen
-
-                    let idx = idx - h_lines_count;
-
-                    // In vertical layout x coord for v_lines must be corrected
-                    let mut x = d.x;
-                    match node.as_ref() {
-                        Node::HorizontalLine(_) => {
-                            h_lines_count += 1;
-                        }
-                        Node::VerticalLine(_) => {
-                            h_lines_count += 1;
-                            x = d.x + d.width / 2;
-                        }
-                        _ => (),
-                    };
-
-                    invalidate_dimensions(
-                        node,
-                        &Dimension {
-                            y: d.y + idx * height,
-                            height,
-                            x: x,
-                            ..*d
-                        },
-                    )
-                })
-                .collect();
-        }
-        Node::H(nodes) => {
-            // Get number of elements which are NOT {h,v}_lines
-            let len = nodes
-                .iter()
-                .filter(|e| match e.as_ref() {
-                    Node::HorizontalLine(_) | Node::VerticalLine(_) => false,
-                    _ => true,
-                })
-                .count();
-
-            // Split area evenly by nodes which are NOT {h,v}_lines
-            let width = ((d.width / len) as f64).round() as usize;
-
-            let mut h_lines_count = 0;
-
-            let _: Vec<_> = nodes
-                .iter_mut()
-                .enumerate()
-                .map(|(idx, node)| {
-                    let idx = idx - h_lines_count;
-
-                    let mut y = d.y;
-                    match node.as_ref() {
-                        Node::HorizontalLine(_) => {
-                            h_lines_count += 1;
-                            y = d.y + d.height / 2;
-                        }
-                        Node::VerticalLine(_) => {
-                            h_lines_count += 1;
-                        }
-                        _ => (),
-                    };
-
-                    invalidate_dimensions(
-                        node,
-                        &Dimension {
-                            x: d.x + idx * width,
-                            width,
-                            y: y,
-                            ..*d
-                        },
-                    )
-                })
-                .collect();
+        Node::V(layout) => {
+            let padding = layout.attrs.padding.unwrap_or(config.layout_padding);
+            layout.dim = *d;
+            let content = shrink(d, &padding, path)?;
+            let font_size = layout.font_size.or(font_size);
+            invalidate_layout(
+                layout,
+                &content,
+                config,
+                path,
+                font_size,
+                Direction::Vertical,
+            )?
+        }
+        Node::H(layout) => {
+            let padding = layout.attrs.padding.unwrap_or(config.layout_padding);
+            layout.dim = *d;
+            let content = shrink(d, &padding, path)?;
+            let font_size = layout.font_size.or(font_size);
+            invalidate_layout(
+                layout,
+                &content,
+                config,
+                path,
+                font_size,
+                Direction::Horizontal,
+            )?
         }
         Node::HH(split) => {
+            let padding = split.attrs.padding.unwrap_or(config.layout_padding);
+            split.dim = *d;
+            let d = &shrink(d, &padding, path)?;
+            let (first_path, second_path) = split_paths(path, split);
+            let font_size = split.font_size.or(font_size);
+
             let up_height = (d.height as f64 * split.first_occupation_percent) as usize;
-            let down_height = d.height - up_height;
-            invalidate_dimensions(
+            let down_height =
+                d.height
+                    .checked_sub(up_height)
+                    .ok_or_else(|| LayoutError::Overflow {
+                        path: path.into(),
+                        needed: up_height,
+                        available: d.height,
+                    })?;
+            invalidate_node(
                 &mut split.first,
                 &Dimension {
                     x: d.x,
@@ -318,8 +1368,11 @@ pub fn invalidate_dimensions(root: &mut Node, d: &Dimension) {
                     width: d.width,
                     height: up_height,
                 },
-            );
-            invalidate_dimensions(
+                config,
+                &first_path,
+                font_size,
+            )?;
+            invalidate_node(
                 &mut split.second,
                 &Dimension {
                     x: d.x,
@@ -327,51 +1380,481 @@ pub fn invalidate_dimensions(root: &mut Node, d: &Dimension) {
                     width: d.width,
                     height: down_height,
                 },
-            );
+                config,
+                &second_path,
+                font_size,
+            )?;
+        }
+        Node::VV(split) => {
+            let padding = split.attrs.padding.unwrap_or(config.layout_padding);
+            split.dim = *d;
+            let d = &shrink(d, &padding, path)?;
+            let (first_path, second_path) = split_paths(path, split);
+            let font_size = split.font_size.or(font_size);
+
+            let left_width = (d.width as f64 * split.first_occupation_percent) as usize;
+            let right_width =
+                d.width
+                    .checked_sub(left_width)
+                    .ok_or_else(|| LayoutError::Overflow {
+                        path: path.into(),
+                        needed: left_width,
+                        available: d.width,
+                    })?;
+            invalidate_node(
+                &mut split.first,
+                &Dimension {
+                    width: left_width,
+                    ..*d
+                },
+                config,
+                &first_path,
+                font_size,
+            )?;
+            invalidate_node(
+                &mut split.second,
+                &Dimension {
+                    x: d.x + left_width,
+                    width: right_width,
+                    ..*d
+                },
+                config,
+                &second_path,
+                font_size,
+            )?;
         }
         Node::Tile(tile) => {
+            tile.padding = tile.attrs.padding.unwrap_or(config.padding);
             tile.dim = *d;
 
             // let font_size = (tile.dim.width.min(tile.dim.height) as f64 * 0.75) as usize;
-            if tile.text.font_size.is_none() {
-                set_bc_font_size(tile);
+            // Group font goes before the inherited one
+            tile.text.font = match tile.text.font_group {
+                None => tile.text.font_size.or(font_size),
+                Some(_) => tile.text.font_size,
+            };
+            match (tile.text.font, tile.text.font_group) {
+                (Some(_), _) => align_text(tile, &config.fonts, path)?,
+                // Font is known once all tiles in the group are placed
+                (None, Some(_)) => {}
+                (None, None) => {
+                    set_bc_font_size(tile, &config.fonts);
+                    align_text(tile, &config.fonts, path)?;
+                }
             }
-
-            center_text(tile);
         }
-        Node::HorizontalLine(dim) => {
-            const MARGIN: usize = 13;
-            dim.x = d.x + MARGIN;
-            dim.y = d.y;
-            dim.width = d.width - 2 * MARGIN;
-            dim.height = 1;
+        Node::HorizontalLine(line) => {
+            let margin = line.margin.unwrap_or(config.h_line_margin);
+            let dim = shrink(
+                &Dimension { height: 1, ..*d },
+                &margin.without_vertical(),
+                path,
+            )?;
+            line.dim = Dimension {
+                y: d.y + margin.top,
+                ..dim
+            };
         }
-        Node::VerticalLine(dim) => {
-            const MARGIN: usize = 3;
-            dim.x = d.x;
-            dim.y = d.y + MARGIN;
-            dim.width = 1;
-            dim.height = d.height - 2 * MARGIN;
+        Node::VerticalLine(line) => {
+            let margin = line.margin.unwrap_or(config.v_line_margin);
+            let dim = shrink(
+                &Dimension { width: 1, ..*d },
+                &margin.without_horizontal(),
+                path,
+            )?;
+            line.dim = Dimension {
+                x: d.x + margin.left,
+                ..dim
+            };
         }
         Node::VListWidget(list) => {
+            list.padding = list.attrs.padding.unwrap_or(config.padding);
             list.dim = *d;
+            list.font = list.font_size.or(font_size);
 
-            const MARGIN: usize = 1;
+            if list.elements.is_empty() || list.visible_elements == 0 {
+                return Err(LayoutError::Empty { path: path.into() });
+            }
+            if let Some(selected) = list.selected.filter(|&idx| idx >= list.elements.len()) {
+                return Err(LayoutError::NoSuchElement {
+                    path: path.into(),
+                    selected,
+                    elements: list.elements.len(),
+                });
+            }
+
+            let content = shrink(d, &list.padding, path)?;
 
-            let tile_height = d.height / list.visible_elements;
+            let heights = distribute(
+                content.height,
+                &vec![Size::default(); list.visible_elements],
+                path,
+            )?;
 
+            let mut slots = Vec::with_capacity(heights.len());
+            let mut y = content.y;
+            for height in heights {
+                slots.push((y, height));
+                y += height;
+            }
+
+            // Elements past the visible ones are scrolled into the same slots
             for (idx, tile) in &mut list.elements.iter_mut().enumerate() {
-                let offset = idx * tile_height;
+                let (y, height) = slots[idx % slots.len()];
 
                 tile.dim = Dimension {
-                    x: list.dim.x + MARGIN,
-                    y: d.y + offset,
-                    width: list.dim.width - 2 * MARGIN,
-                    height: tile_height,
+                    y,
+                    height,
+                    ..content
                 };
+                tile.text.font = tile.text.font_size.or(list.font);
+
+                let tile_path = element_path(path, idx, tile);
+                align_text(tile, &config.fonts, &tile_path)?;
+            }
+        }
+    }
+
+    Ok(())
+}
 
-                center_text(tile)
+/// Gives every font group the biggest font which fits all its tiles,
+/// tiles with the font set explicitly leave the group
+fn resolve_font_groups(root: &mut Node, fonts: &FontSet, path: &str) -> Result<(), LayoutError> {
+    let mut groups: HashMap<&'static str, usize> = HashMap::new();
+    visit_tiles_mut(root, path, &mut |tile, _| {
+        if let (None, Some(group)) = (tile.text.font, tile.text.font_group) {
+            if let Some(size) = fitting_bc_font_size(tile, fonts) {
+                let common = groups.entry(group).or_insert(size);
+                *common = (*common).min(size);
             }
         }
+        Ok(())
+    })?;
+
+    visit_tiles_mut(root, path, &mut |tile, path| {
+        if let (None, Some(group)) = (tile.text.font, tile.text.font_group) {
+            tile.text.font = groups.get(group).copied();
+            align_text(tile, fonts, path)?;
+        }
+        Ok(())
+    })
+}
+
+/// Dynamic tiles and lists must not refresh over the lines and labels,
+/// which are drawn only once, nor over the text of each other
+fn check_refresh_areas(root: &Node, path: &str) -> Result<(), LayoutError> {
+    // Refreshed and content areas of the dynamic widgets,
+    // and areas of the static elements, with their paths
+    let mut refreshed: Vec<(String, Dimension, Dimension)> = Vec::new();
+    let mut statics: Vec<(String, Dimension)> = Vec::new();
+    walk(root, path, &mut |node, path| match node {
+        Node::Tile(tile) if tile.text.is_dynamic() => {
+            refreshed.push((path.into(), tile.refresh, tile.content()))
+        }
+        Node::Tile(tile) => statics.push((path.into(), tile.content())),
+        Node::VListWidget(list) => refreshed.push((path.into(), list.refresh, list.content())),
+        Node::HorizontalLine(line) | Node::VerticalLine(line) => {
+            statics.push((path.into(), line.dim))
+        }
+        Node::V(_) | Node::H(_) | Node::HH(_) | Node::VV(_) => {}
+    });
+
+    for (idx, (path, refresh, _)) in refreshed.iter().enumerate() {
+        let wiped_static = statics
+            .iter()
+            .find(|(_, element)| refresh.overlaps(element))
+            .map(|(element, _)| element);
+        let wiped_tile = refreshed
+            .iter()
+            .enumerate()
+            .find(|&(other, (_, _, content))| other != idx && refresh.overlaps(content))
+            .map(|(_, (element, _, _))| element);
+
+        if let Some(element) = wiped_static.or(wiped_tile) {
+            return Err(LayoutError::RefreshOverlaps {
+                path: path.clone(),
+                element: element.clone(),
+            });
+        }
     }
+    Ok(())
+}
+
+/// Sets areas refreshed by tiles and lists, their content aligned for the display
+fn align_refresh_areas(root: &mut Node, path: &str, alignment: usize, bounds: &Dimension) {
+    walk_mut(root, path, &mut |node, _| match node {
+        Node::Tile(tile) => tile.refresh = tile.content().aligned(alignment, bounds),
+        Node::VListWidget(list) => list.refresh = list.content().aligned(alignment, bounds),
+        _ => {}
+    });
+}
+
+/// Calls f on every Tile in the tree, including list elements,
+/// stops at the first error
+fn visit_tiles_mut(
+    root: &mut Node,
+    path: &str,
+    f: &mut dyn FnMut(&mut Tile, &str) -> Result<(), LayoutError>,
+) -> Result<(), LayoutError> {
+    let mut result = Ok(());
+    walk_mut(root, path, &mut |node, path| {
+        if result.is_err() {
+            return;
+        }
+        result = match node {
+            Node::Tile(tile) => f(tile, path),
+            Node::VListWidget(list) => {
+                list.elements
+                    .iter_mut()
+                    .enumerate()
+                    .try_for_each(|(idx, tile)| {
+                        let tile_path = element_path(path, idx, tile);
+                        f(tile, &tile_path)
+                    })
+            }
+            _ => Ok(()),
+        };
+    });
+    result
+}
+
+/// Calls f on the node and on every node below it, parents first, with their paths
+fn walk(root: &Node, path: &str, f: &mut impl FnMut(&Node, &str)) {
+    f(root, path);
+    match root {
+        Node::V(layout) | Node::H(layout) => {
+            for (pos, node) in layout.nodes.iter().enumerate() {
+                walk(node, &child_path(path, pos, node), f);
+            }
+        }
+        Node::HH(split) | Node::VV(split) => {
+            let (first_path, second_path) = split_paths(path, split);
+            walk(&split.first, &first_path, f);
+            walk(&split.second, &second_path, f);
+        }
+        Node::Tile(_) | Node::HorizontalLine(_) | Node::VerticalLine(_) | Node::VListWidget(_) => {}
+    }
+}
+
+/// Same as walk, but f may change the nodes
+fn walk_mut(root: &mut Node, path: &str, f: &mut impl FnMut(&mut Node, &str)) {
+    f(root, path);
+    match root {
+        Node::V(layout) | Node::H(layout) => {
+            for (pos, node) in layout.nodes.iter_mut().enumerate() {
+                let node_path = child_path(path, pos, node);
+                walk_mut(node, &node_path, f);
+            }
+        }
+        Node::HH(split) | Node::VV(split) => {
+            let (first_path, second_path) = split_paths(path, split);
+            walk_mut(&mut split.first, &first_path, f);
+            walk_mut(&mut split.second, &second_path, f);
+        }
+        Node::Tile(_) | Node::HorizontalLine(_) | Node::VerticalLine(_) | Node::VListWidget(_) => {}
+    }
+}
+
+/// Path of the root node, root/V
+fn root_path(root: &Node) -> String {
+    format!("root/{}", root.label())
+}
+
+/// Path of the child of V or H layout, root/V[2]/Tile("Paused")
+fn child_path(path: &str, pos: usize, node: &Node) -> String {
+    format!("{}[{}]/{}", path, pos, node.label())
+}
+
+/// Paths of both parts of the split, root/HH.first/V and root/HH.second/V
+fn split_paths(path: &str, split: &HardSplit) -> (String, String) {
+    (
+        format!("{}.first/{}", path, split.first.label()),
+        format!("{}.second/{}", path, split.second.label()),
+    )
+}
+
+/// Path of the list element, root/VListWidget[0]/Tile("Resume")
+fn element_path(path: &str, idx: usize, tile: &Tile) -> String {
+    format!("{}[{}]/Tile({:?})", path, idx, tile.text.name)
+}
+
+/// Splits area of V or H layout among its children.
+///
+/// {h,v}_lines do not take any space, they are drawn on top of other widgets.
+/// Line along the layout (h_line in V) goes on the boundary between the widgets
+/// around it. Line across the layout (v_line in V) splits the widget which
+/// follows it, where that widget is divided:
+/// [  widget1  ]
+/// [-h_line----]
+/// [ w2 | w3   ] <- H preceded by v_line
+fn invalidate_layout(
+    layout: &mut Layout,
+    d: &Dimension,
+    config: &LayoutConfig,
+    path: &str,
+    font_size: Option<usize>,
+    direction: Direction,
+) -> Result<(), LayoutError> {
+    let sizes: Vec<_> = layout
+        .nodes
+        .iter()
+        .filter_map(|node| node.attrs())
+        .map(|attrs| match direction {
+            Direction::Vertical => attrs.height,
+            Direction::Horizontal => attrs.width,
+        })
+        .collect();
+
+    if sizes.is_empty() {
+        return Err(LayoutError::Empty { path: path.into() });
+    }
+
+    let extent = match direction {
+        Direction::Vertical => d.height,
+        Direction::Horizontal => d.width,
+    };
+    let lengths = distribute(extent, &sizes, path)?;
+
+    let mut offset = 0;
+    let widgets = layout
+        .nodes
+        .iter_mut()
+        .enumerate()
+        .filter(|(_, node)| node.attrs().is_some());
+
+    for ((pos, node), length) in widgets.zip(lengths) {
+        let node_path = child_path(path, pos, node);
+        let attrs = *node.attrs().unwrap();
+        let dim = match direction {
+            Direction::Vertical => Dimension {
+                x: d.x,
+                y: d.y + offset,
+                width: attrs.width.cross(d.width),
+                height: length,
+            },
+            Direction::Horizontal => Dimension {
+                x: d.x + offset,
+                y: d.y,
+                width: length,
+                height: attrs.height.cross(d.height),
+            },
+        };
+
+        invalidate_node(node, &dim, config, &node_path, font_size)?;
+        offset += length;
+    }
+
+    for pos in 0..layout.nodes.len() {
+        let node = &layout.nodes[pos];
+        if node.attrs().is_some() {
+            continue;
+        }
+
+        let node_path = child_path(path, pos, node);
+        let next = layout.nodes[pos..]
+            .iter()
+            .find(|node| node.attrs().is_some());
+
+        // Line after the last widget goes on the last row, or column, of the layout
+        let area = match (direction, node) {
+            (Direction::Vertical, Node::HorizontalLine(_)) => Dimension {
+                y: next.map_or(d.y + d.height.saturating_sub(1), |next| next.slot(config).y),
+                ..*d
+            },
+            (Direction::Horizontal, Node::VerticalLine(_)) => Dimension {
+                x: next.map_or(d.x + d.width.saturating_sub(1), |next| next.slot(config).x),
+                ..*d
+            },
+            (Direction::Vertical, _) => {
+                let next = next.ok_or_else(|| LayoutError::NothingToSeparate {
+                    path: node_path.clone(),
+                })?;
+                let x = next
+                    .boundary(Direction::Horizontal, config)
+                    .ok_or_else(|| LayoutError::NothingToSeparate {
+                        path: node_path.clone(),
+                    })?;
+                Dimension {
+                    x,
+                    ..next.slot(config)
+                }
+            }
+            (Direction::Horizontal, _) => {
+                let next = next.ok_or_else(|| LayoutError::NothingToSeparate {
+                    path: node_path.clone(),
+                })?;
+                let y = next.boundary(Direction::Vertical, config).ok_or_else(|| {
+                    LayoutError::NothingToSeparate {
+                        path: node_path.clone(),
+                    }
+                })?;
+                Dimension {
+                    y,
+                    ..next.slot(config)
+                }
+            }
+        };
+
+        invalidate_node(&mut layout.nodes[pos], &area, config, &node_path, None)?;
+    }
+
+    Ok(())
+}
+
+/// Collects dimensions of nodes which are not split any further,
+/// {h,v}_lines are drawn on top of them, so they are skipped
+#[cfg(test)]
+pub fn leaf_dimensions(root: &Node) -> Vec<Dimension> {
+    let mut leaves = Vec::new();
+    walk(root, &root_path(root), &mut |node, _| {
+        if let Node::Tile(_) | Node::VListWidget(_) = node {
+            leaves.push(node.dim());
+        }
+    });
+    leaves
+}
+
+/// Font chosen for each font group in the tree
+pub fn font_groups(root: &Node) -> BTreeMap<&'static str, usize> {
+    let mut groups = BTreeMap::new();
+    walk(root, &root_path(root), &mut |node, _| {
+        // Tiles with the font set explicitly are not in the group
+        if let Node::Tile(tile) = node {
+            if let (Some(group), None, Some(size)) =
+                (tile.text.font_group, tile.text.font_size, tile.text.font)
+            {
+                groups.insert(group, size);
+            }
+        }
+    });
+    groups
+}
+
+/// Checks leaves tile the root area exactly: every leaf is inside the root,
+/// leaves do not overlap and there are no gaps between them
+#[cfg(test)]
+pub fn assert_leaves_cover(root: &Node, d: &Dimension) {
+    let leaves = leaf_dimensions(root);
+
+    for leaf in &leaves {
+        assert!(
+            leaf.x >= d.x
+                && leaf.y >= d.y
+                && leaf.x + leaf.width <= d.x + d.width
+                && leaf.y + leaf.height <= d.y + d.height,
+            "{:?} goes beyond {:?}",
+            leaf,
+            d
+        );
+    }
+
+    for (idx, a) in leaves.iter().enumerate() {
+        for b in &leaves[idx + 1..] {
+            assert!(!a.overlaps(b), "{:?} overlaps {:?}", a, b);
+        }
+    }
+
+    let area: usize = leaves.iter().map(|leaf| leaf.width * leaf.height).sum();
+    assert_eq!(area, d.width * d.height, "leaves leave gaps in {:?}", d);
 }
@@ -0,0 +1,95 @@
+use std::path::Path;
+
+use crate::{
+    common::{Dimension, Edges, LayoutConfig},
+    fonts::{FontError, FontSet},
+};
+
+/// C++ names of the firmware the views are generated for
+#[derive(Debug, Clone)]
+pub struct CodegenTarget {
+    /// Base class of the views
+    pub view_base: &'static str,
+    /// Values shown by the views, passed to the draw methods
+    pub view_data: &'static str,
+    /// Display the view draws on, passed to its ctor
+    pub display: &'static str,
+}
+
+impl CodegenTarget {
+    /// Firmware of the bike computer, views draw with Paint of the display
+    pub fn bike_computer() -> Self {
+        CodegenTarget {
+            view_base: "View",
+            view_data: "ViewData",
+            display: "IDisplay",
+        }
+    }
+}
+
+/// Everything the pipeline needs to know about the display,
+/// from the layout up to the generated code
+#[derive(Debug, Clone)]
+pub struct DisplayProfile {
+    pub name: &'static str,
+    /// Resolution, the whole area of the page
+    pub dim: Dimension,
+    /// Bits per pixel
+    pub color_depth: usize,
+    /// Fonts, default margins and paddings, refresh alignment
+    pub layout: LayoutConfig,
+    pub target: CodegenTarget,
+}
+
+impl DisplayProfile {
+    /// Sharp Memory LCD 2.7"
+    pub fn sharp_mip_2in7() -> Self {
+        DisplayProfile {
+            name: "Sharp Memory LCD 2.7\"",
+            dim: Dimension {
+                x: 0,
+                y: 0,
+                width: 400,
+                height: 240,
+            },
+            color_depth: 1,
+            // Defaults of the layout are the ones of this display
+            layout: LayoutConfig::default(),
+            target: CodegenTarget::bike_computer(),
+        }
+    }
+
+    /// Waveshare e-Paper 2.9"
+    pub fn waveshare_2in9() -> Self {
+        DisplayProfile {
+            name: "Waveshare e-Paper 2.9\"",
+            dim: Dimension {
+                x: 0,
+                y: 0,
+                width: 296,
+                height: 128,
+            },
+            color_depth: 1,
+            layout: LayoutConfig {
+                fonts: FontSet::waveshare(),
+                // Controller updates whole bytes of 1bpp rows, padding as wide
+                // keeps the aligned refresh inside of the tile
+                refresh_alignment: 8,
+                padding: Edges::symmetric(1, 8),
+                margin: Edges::default(),
+                layout_padding: Edges::default(),
+                h_line_margin: Edges::symmetric(0, 13),
+                v_line_margin: Edges::symmetric(3, 0),
+                strict_glyphs: false,
+            },
+            target: CodegenTarget::bike_computer(),
+        }
+    }
+
+    /// Same display with the fonts of the firmware, every FontNN.cpp
+    /// from the directory, instead of the built-in metrics
+    pub fn with_fonts_from(mut self, dir: &Path) -> Result<Self, FontError> {
+        self.layout.fonts = FontSet::load(dir)?;
+        Ok(self)
+    }
+}
@@ -0,0 +1,588 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryFrom,
+    fmt, fs,
+    path::Path,
+};
+
+/// Metrics of a single bitmap font available on the device
+#[derive(Debug, Clone, PartialEq)]
+pub struct Font {
+    /// Size font is referred by in the firmware, 24 for Font24
+    pub size: usize,
+    /// Advance of glyphs without their own advance,
+    /// that is every glyph of monospace font
+    pub width: usize,
+    /// Height of the glyph box
+    pub height: usize,
+    /// Distance from the top of the glyph box to the baseline
+    pub ascent: usize,
+    /// Characters font has glyphs for
+    pub glyphs: BTreeSet<char>,
+    /// Advance of each glyph of proportional font
+    pub advances: BTreeMap<char, usize>,
+    /// Adjustment of the advance between pairs of glyphs
+    pub kerning: BTreeMap<(char, char), isize>,
+}
+
+impl Font {
+    /// Font covering printable ASCII, the way firmware fonts do.
+    /// Height is the size, ascent is not known, so descent is
+    /// assumed to take 1/5 of the height
+    pub fn monospace(size: usize, width: usize) -> Self {
+        Font {
+            size,
+            width,
+            height: size,
+            ascent: size - size / 5,
+            glyphs: ascii(95),
+            advances: BTreeMap::new(),
+            kerning: BTreeMap::new(),
+        }
+    }
+
+    /// Sets advances of the glyphs, making the font proportional.
+    /// Glyphs without their own advance keep the width of the font
+    pub fn with_advances<T>(mut self, advances: T) -> Self
+    where
+        T: IntoIterator<Item = (char, usize)>,
+    {
+        self.advances.extend(advances);
+        self
+    }
+
+    /// Sets kerning of the glyph pairs, negative moves glyphs closer
+    pub fn with_kerning<T>(mut self, pairs: T) -> Self
+    where
+        T: IntoIterator<Item = ((char, char), isize)>,
+    {
+        self.kerning.extend(pairs);
+        self
+    }
+
+    /// Parses font from the firmware sources, like Font24.cpp:
+    ///
+    /// const uint8_t Font24_Table [] = { 0x00, 0x00, 0x00, ... };
+    /// sFONT Font24 = { Font24_Table, 17, /* Width */ 24, /* Height */ };
+    ///
+    /// Table holds glyphs of consecutive characters starting from ' ',
+    /// each glyph is height rows of width bits, rows padded to whole bytes.
+    ///
+    /// Proportional font has advance of every glyph of the table,
+    /// and may have adjustments of the advance between glyph pairs:
+    ///
+    /// const uint8_t Font24_Widths [] = { 6, 4, 8, ... };
+    /// const int8_t Font24_Kerning [][3] = { {'A', 'V', -2}, ... };
+    pub fn from_c_source(source: &str) -> Result<Self, FontError> {
+        let code = strip_comments(source);
+        let malformed = |font: &str, reason| FontError::Malformed {
+            font: font.into(),
+            reason,
+        };
+
+        let (_, definition) = code
+            .split_once("sFONT")
+            .ok_or_else(|| malformed("sFONT", "there is no sFONT definition"))?;
+        let (name, fields) = definition
+            .split_once('=')
+            .ok_or_else(|| malformed("sFONT", "sFONT is not initialized"))?;
+        let name = name.trim();
+        let size = name
+            .trim_start_matches(|c: char| !c.is_ascii_digit())
+            .parse()
+            .map_err(|_| malformed(name, "font name does not end with its size"))?;
+
+        let fields: Vec<_> = between(fields, '{', '}')
+            .ok_or_else(|| malformed(name, "sFONT is not initialized"))?
+            .split(',')
+            .map(str::trim)
+            .collect();
+        let (table_name, width, height) = match fields[..] {
+            [table_name, width, height, ..] => (table_name, width, height),
+            _ => return Err(malformed(name, "sFONT needs table, width and height")),
+        };
+        let width: usize = width
+            .parse()
+            .map_err(|_| malformed(name, "width is not a number"))?;
+        let height: usize = height
+            .parse()
+            .map_err(|_| malformed(name, "height is not a number"))?;
+
+        let bytes = array(&code, table_name)
+            .ok_or_else(|| malformed(name, "glyph table is missing"))?
+            .iter()
+            .map(|byte| value(byte).and_then(|byte| u8::try_from(byte).ok()))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| malformed(name, "glyph table holds something else than bytes"))?;
+
+        let row_len = width.div_ceil(8);
+        let glyph_len = row_len * height;
+        if glyph_len == 0 || bytes.is_empty() || bytes.len() % glyph_len != 0 {
+            return Err(malformed(
+                name,
+                "glyph table does not match width and height",
+            ));
+        }
+
+        // Ascent is the bottom row of 'H', it has no descent
+        let h = ('H' as usize) - (' ' as usize);
+        let ascent = bytes
+            .chunks(glyph_len)
+            .nth(h)
+            .and_then(|glyph| {
+                glyph
+                    .chunks(row_len)
+                    .rposition(|row| row.iter().any(|&b| b != 0))
+            })
+            .map_or(height - height / 5, |row| row + 1);
+
+        let glyphs = ascii(bytes.len() / glyph_len);
+
+        let advances = match array(&code, &format!("{}_Widths", name)) {
+            Some(widths) => widths
+                .iter()
+                .map(|width| value(width).and_then(|width| usize::try_from(width).ok()))
+                .collect::<Option<Vec<_>>>()
+                .filter(|widths| widths.len() == glyphs.len())
+                .ok_or_else(|| malformed(name, "widths do not match the glyph table"))?,
+            None => Vec::new(),
+        };
+
+        // Pair is given by three values, the glyphs and the adjustment
+        let pair = |values: &[&str]| match *values {
+            [first, second, adjustment] => {
+                let glyph = |text| value(text).and_then(|c| char::from_u32(c as u32));
+                Some(((glyph(first)?, glyph(second)?), value(adjustment)? as isize))
+            }
+            _ => None,
+        };
+        let kerning = match array(&code, &format!("{}_Kerning", name)) {
+            Some(values) => values
+                .chunks(3)
+                .map(pair)
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| malformed(name, "kerning holds something else than pairs"))?,
+            None => Vec::new(),
+        };
+
+        let font = Font {
+            size,
+            width,
+            height,
+            ascent,
+            glyphs: glyphs.clone(),
+            advances: BTreeMap::new(),
+            kerning: BTreeMap::new(),
+        };
+        Ok(font
+            .with_advances(glyphs.into_iter().zip(advances))
+            .with_kerning(kerning))
+    }
+
+    /// Amount of pixels glyph moves the pen
+    pub fn advance(&self, c: char) -> usize {
+        self.advances.get(&c).copied().unwrap_or(self.width)
+    }
+
+    /// Amount of pixels text takes in one line
+    pub fn text_width(&self, text: &str) -> usize {
+        let advances: usize = text.chars().map(|c| self.advance(c)).sum();
+        let kerning: isize = text
+            .chars()
+            .zip(text.chars().skip(1))
+            .filter_map(|pair| self.kerning.get(&pair))
+            .sum();
+
+        (advances as isize + kerning).max(0) as usize
+    }
+
+    /// Amount of leading characters of the text,
+    /// which fit in the width along with the suffix
+    pub fn fitting_chars(&self, text: &str, suffix: &str, width: usize) -> usize {
+        let mut prefix = String::new();
+
+        for (count, c) in text.chars().enumerate() {
+            prefix.push(c);
+            if self.text_width(&(prefix.clone() + suffix)) > width {
+                return count;
+            }
+        }
+
+        text.chars().count()
+    }
+
+    /// Splits text into lines not wider than width, breaking between words.
+    /// Word wider than the width takes a line on its own
+    pub fn wrap<'a>(&self, text: &'a str, width: usize) -> Vec<&'a str> {
+        let mut lines = Vec::new();
+        let mut line: Option<(usize, usize)> = None;
+
+        for word in text.split_whitespace() {
+            // Words are slices of the text, so lines can be as well
+            let start = word.as_ptr() as usize - text.as_ptr() as usize;
+            let end = start + word.len();
+
+            line = match line {
+                Some((line_start, _)) if self.text_width(&text[line_start..end]) <= width => {
+                    Some((line_start, end))
+                }
+                Some((line_start, line_end)) => {
+                    lines.push(&text[line_start..line_end]);
+                    Some((start, end))
+                }
+                None => Some((start, end)),
+            };
+        }
+        lines.extend(line.map(|(start, end)| &text[start..end]));
+
+        lines
+    }
+}
+
+/// Fonts available on the display
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FontSet {
+    // Sorted by size, smallest first
+    fonts: Vec<Font>,
+}
+
+impl FontSet {
+    pub fn new<T>(fonts: T) -> Self
+    where
+        T: IntoIterator<Item = Font>,
+    {
+        let mut fonts: Vec<_> = fonts.into_iter().collect();
+        fonts.sort_by_key(|font| font.size);
+        fonts.dedup_by_key(|font| font.size);
+
+        FontSet { fonts }
+    }
+
+    /// Fonts of the bike computer on Sharp MIP display
+    pub fn sharp_mip() -> Self {
+        FontSet::new(
+            [(19, 11), (24, 14), (31, 18), (42, 24), (56, 32)]
+                .iter()
+                .map(|&(size, width)| Font::monospace(size, width)),
+        )
+    }
+
+    /// Fonts shipped with Waveshare e-paper examples
+    pub fn waveshare() -> Self {
+        FontSet::new(
+            [(8, 5), (12, 7), (16, 11), (20, 14), (24, 17)]
+                .iter()
+                .map(|&(size, width)| Font::monospace(size, width)),
+        )
+    }
+
+    /// Loads every FontNN.cpp from the directory
+    pub fn load(dir: &Path) -> Result<Self, FontError> {
+        let io_error = |err: std::io::Error| FontError::Io {
+            path: dir.display().to_string(),
+            reason: err.to_string(),
+        };
+
+        let mut fonts = Vec::new();
+        for entry in fs::read_dir(dir).map_err(io_error)? {
+            let path = entry.map_err(io_error)?.path();
+            let name = path.file_name().and_then(|name| name.to_str());
+            if !name.is_some_and(|name| name.starts_with("Font") && name.ends_with(".cpp")) {
+                continue;
+            }
+
+            let source = fs::read_to_string(&path).map_err(|err| FontError::Io {
+                path: path.display().to_string(),
+                reason: err.to_string(),
+            })?;
+            fonts.push(Font::from_c_source(&source)?);
+        }
+
+        Ok(FontSet::new(fonts))
+    }
+
+    pub fn get(&self, size: usize) -> Option<&Font> {
+        self.fonts.iter().find(|font| font.size == size)
+    }
+
+    pub fn biggest_first(&self) -> impl Iterator<Item = &Font> {
+        self.fonts.iter().rev()
+    }
+
+    pub fn smallest(&self) -> Option<&Font> {
+        self.fonts.first()
+    }
+}
+
+/// Font sources which cannot be used
+#[derive(Debug, PartialEq)]
+pub enum FontError {
+    Io { path: String, reason: String },
+    Malformed { font: String, reason: &'static str },
+}
+
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FontError::Io { path, reason } => write!(f, "{}: {}", path, reason),
+            FontError::Malformed { font, reason } => write!(f, "{}: {}", font, reason),
+        }
+    }
+}
+
+impl std::error::Error for FontError {}
+
+/// count consecutive characters starting from ' '
+fn ascii(count: usize) -> BTreeSet<char> {
+    (' '..).take(count).collect()
+}
+
+/// Values the array is initialized with, nested braces are flattened:
+/// const int8_t Font24_Kerning [][3] = { {'A', 'V', -2} } gives 'A', 'V', -2
+fn array<'a>(code: &'a str, name: &str) -> Option<Vec<&'a str>> {
+    let start = code
+        .find(&format!("{}[", name))
+        .or_else(|| code.find(&format!("{} [", name)))?;
+    let (_, initializer) = code[start..].split_once('=')?;
+    let (values, _) = initializer.split_once(';')?;
+
+    Some(
+        values
+            .split([',', '{', '}'])
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .collect(),
+    )
+}
+
+/// Number written in C, decimal, hex like 0x1F or character like 'A'
+fn value(text: &str) -> Option<i64> {
+    if let Some(hex) = text.strip_prefix("0x") {
+        return i64::from_str_radix(hex, 16).ok();
+    }
+    match text.strip_prefix('\'').and_then(|c| c.strip_suffix('\'')) {
+        Some(c) if c.chars().count() == 1 => c.chars().next().map(|c| c as i64),
+        Some(_) => None,
+        None => text.parse().ok(),
+    }
+}
+
+/// Text between the first open and the following close
+fn between(text: &str, open: char, close: char) -> Option<&str> {
+    let (_, rest) = text.split_once(open)?;
+    let (inside, _) = rest.split_once(close)?;
+    Some(inside)
+}
+
+fn strip_comments(source: &str) -> String {
+    let mut code = String::with_capacity(source.len());
+    let mut rest = source;
+
+    while let Some(start) = rest.find("//").into_iter().chain(rest.find("/*")).min() {
+        code.push_str(&rest[..start]);
+        let end = if rest[start..].starts_with("//") {
+            rest[start..].find('\n').map(|end| start + end)
+        } else {
+            rest[start..].find("*/").map(|end| start + end + 2)
+        };
+        rest = end.map_or("", |end| &rest[end..]);
+    }
+    code.push_str(rest);
+
+    code
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // First three glyphs of 5x8 font and 'H', the rest is cut off
+    const FONT8: &str = r#"
+#include "fonts.h"
+
+//
+//  Font data for Courier New 12pt
+//
+
+const uint8_t Font8_Table [] =
+{
+	// @0 ' ' (5 pixels wide)
+	0x00, //
+	0x00, //
+	0x00, //
+	0x00, //
+	0x00, //
+	0x00, //
+	0x00, //
+	0x00, //
+
+	// @8 '!' (5 pixels wide)
+	0x20, //   #
+	0x20, //   #
+	0x20, //   #
+	0x20, //   #
+	0x00, //
+	0x20, //   #
+	0x00, //
+	0x00, //
+};
+
+sFONT Font8 = {
+  Font8_Table,
+  5, /* Width */
+  8, /* Height */
+};
+"#;
+
+    #[test]
+    fn parses_firmware_font_source() {
+        let font = Font::from_c_source(FONT8).unwrap();
+
+        assert_eq!(font.size, 8);
+        assert_eq!(font.width, 5);
+        assert_eq!(font.height, 8);
+        assert_eq!(font.glyphs, [' ', '!'].iter().copied().collect());
+        // There is no 'H' to measure, ascent is guessed
+        assert_eq!(font.ascent, 7);
+        assert_eq!(font.text_width("!!!"), 15);
+    }
+
+    #[test]
+    fn ascent_is_measured_on_h() {
+        // 2 byte rows, 'H' ends on the 3rd row out of 4
+        let mut table = vec!["0x00"; ('H' as usize - ' ' as usize) * 8];
+        table.extend([
+            "0xC3", "0x00", "0xFF", "0x00", "0xC3", "0x00", "0x00", "0x00",
+        ]);
+        let source = format!(
+            "const uint8_t Font4_Table[] = {{{}}};\nsFONT Font4 = {{ Font4_Table, 9, 4 }};",
+            table.join(", ")
+        );
+
+        let font = Font::from_c_source(&source).unwrap();
+
+        assert_eq!(font.ascent, 3);
+        assert!(font.glyphs.contains(&'H'));
+        assert!(!font.glyphs.contains(&'I'));
+    }
+
+    #[test]
+    fn broken_sources_are_reported() {
+        assert_eq!(
+            Font::from_c_source(&FONT8.replace("8, /* Height */", "7, /* Height */")),
+            Err(FontError::Malformed {
+                font: "Font8".into(),
+                reason: "glyph table does not match width and height"
+            })
+        );
+        assert_eq!(
+            Font::from_c_source("const uint8_t Font8_Table [] = {};"),
+            Err(FontError::Malformed {
+                font: "sFONT".into(),
+                reason: "there is no sFONT definition"
+            })
+        );
+    }
+
+    #[test]
+    fn fonts_are_loaded_from_the_directory() {
+        let dir = std::env::temp_dir().join(format!("bc-gui-maker-fonts-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("Font8.cpp"), FONT8).unwrap();
+        fs::write(
+            dir.join("Font12.cpp"),
+            FONT8.replace("Font8", "Font12") + "const uint8_t Font12_Widths [] = { 2, 3 };",
+        )
+        .unwrap();
+        fs::write(dir.join("fonts.h"), "typedef struct _tFont sFONT;").unwrap();
+        fs::write(dir.join("Font8.h"), "extern sFONT Font8;").unwrap();
+        fs::write(
+            dir.join("Font16.cpp"),
+            FONT8
+                .replace("Font8", "Font16")
+                .replace("8, /* Height */", "7, /* Height */"),
+        )
+        .unwrap();
+
+        let broken = FontSet::load(&dir);
+        fs::remove_file(dir.join("Font16.cpp")).unwrap();
+        let fonts = FontSet::load(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            broken,
+            Err(FontError::Malformed {
+                font: "Font16".into(),
+                reason: "glyph table does not match width and height"
+            })
+        );
+        let fonts = fonts.unwrap();
+        let sizes: Vec<_> = fonts.biggest_first().map(|font| font.size).collect();
+        assert_eq!(sizes, vec![12, 8]);
+        assert_eq!(fonts.get(12).unwrap().text_width("!!"), 6);
+        assert_eq!(fonts.get(8).unwrap().text_width("!!"), 10);
+
+        assert!(matches!(
+            FontSet::load(&dir.join("missing")),
+            Err(FontError::Io { .. })
+        ));
+    }
+
+    #[test]
+    fn proportional_text_width_sums_advances_and_kerning() {
+        let font = Font::monospace(16, 10)
+            .with_advances([('A', 10), ('V', 10), ('i', 3), (' ', 4)])
+            .with_kerning([(('A', 'V'), -2), (('V', 'A'), -2)]);
+
+        assert_eq!(font.text_width("ii"), 6);
+        assert_eq!(font.text_width("AVA"), 26);
+        assert_eq!(font.text_width("A i"), 17);
+        // Glyph without its own advance takes the width of the font
+        assert_eq!(font.text_width("?"), 10);
+    }
+
+    #[test]
+    fn proportional_font_source_has_widths_and_kerning() {
+        let source = format!(
+            "{}\nconst uint8_t Font8_Widths [] = {{ 2, 0x03 }};\n\
+             const int8_t Font8_Kerning [][3] = {{ {{'!', '!', -1}}, {{32, '!', 1}} }};",
+            FONT8
+        );
+
+        let font = Font::from_c_source(&source).unwrap();
+
+        assert_eq!(font.width, 5);
+        assert_eq!(font.advance(' '), 2);
+        assert_eq!(font.advance('!'), 3);
+        assert_eq!(font.text_width("!!!"), 7);
+        assert_eq!(font.text_width(" !"), 6);
+
+        // Every glyph of the table needs its width
+        assert_eq!(
+            Font::from_c_source(&source.replace("2, 0x03", "2")),
+            Err(FontError::Malformed {
+                font: "Font8".into(),
+                reason: "widths do not match the glyph table"
+            })
+        );
+        assert_eq!(
+            Font::from_c_source(&source.replace("{32, '!', 1}", "{32, '!'}")),
+            Err(FontError::Malformed {
+                font: "Font8".into(),
+                reason: "kerning holds something else than pairs"
+            })
+        );
+    }
+
+    #[test]
+    fn wrap_breaks_between_words() {
+        let font = Font::monospace(8, 5);
+
+        assert_eq!(
+            font.wrap("Hit button below to calculate your BMI", 90),
+            vec!["Hit button below", "to calculate your", "BMI"]
+        );
+        assert_eq!(font.wrap("  Marathon ", 20), vec!["Marathon"]);
+        assert!(font.wrap(" ", 20).is_empty());
+    }
+}
@@ -0,0 +1,814 @@
+use std::fmt;
+
+/// How the tile turns data into text
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum FormatKind {
+    /// snprintf conversions, like "%5.2f[*C]"
+    #[default]
+    Printf,
+    /// strftime conversions of the wall clock time, like "%d/%m/%y"
+    Strftime,
+    /// Elapsed seconds, "%H" hours (not wrapped at 24), "%M" minutes,
+    /// "%S" seconds, "%T" same as "%H:%M:%S"
+    Duration,
+}
+
+impl fmt::Display for FormatKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatKind::Printf => write!(f, "printf"),
+            FormatKind::Strftime => write!(f, "strftime"),
+            FormatKind::Duration => write!(f, "duration"),
+        }
+    }
+}
+
+/// Single %-sequence of the format
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conversion {
+    /// As it is written in the format, "%5.2f"
+    pub spec: String,
+    pub flags: String,
+    pub width: Option<usize>,
+    pub precision: Option<usize>,
+    /// Length modifier of printf ("l", "hh"), or E/O modifier of strftime
+    pub length: String,
+    pub conversion: char,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Piece {
+    /// Text printed as is, "%%" is already turned into "%"
+    Literal(String),
+    Conversion(Conversion),
+}
+
+/// C++ type of the data field
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CType {
+    U8,
+    U16,
+    I32,
+    U32,
+    I64,
+    U64,
+    F32,
+    F64,
+    /// const char *
+    Str,
+    /// struct tm, for strftime
+    Time,
+}
+
+impl fmt::Display for CType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            CType::U8 => "uint8_t",
+            CType::U16 => "uint16_t",
+            CType::I32 => "int32_t",
+            CType::U32 => "uint32_t",
+            CType::I64 => "int64_t",
+            CType::U64 => "uint64_t",
+            CType::F32 => "float",
+            CType::F64 => "double",
+            CType::Str => "const char *",
+            CType::Time => "struct tm",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Field of the view data printed by the tile, like "gps.in_view"
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Binding {
+    pub field: &'static str,
+    pub ctype: CType,
+    /// Smallest and biggest value of the field, integers default to the whole type
+    pub range: Option<(f64, f64)>,
+}
+
+impl Binding {
+    /// Values the field may take, if they are known
+    pub fn range(&self) -> Option<(f64, f64)> {
+        self.range.or(match self.ctype {
+            CType::U8 => Some((0.0, u8::MAX as f64)),
+            CType::U16 => Some((0.0, u16::MAX as f64)),
+            CType::I32 => Some((i32::MIN as f64, i32::MAX as f64)),
+            CType::U32 => Some((0.0, u32::MAX as f64)),
+            CType::I64 => Some((i64::MIN as f64, i64::MAX as f64)),
+            CType::U64 => Some((0.0, u64::MAX as f64)),
+            _ => None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormatError {
+    /// Conversion which is not valid for the kind of the format
+    Unknown { spec: String, kind: FormatKind },
+    /// Format ends in the middle of a conversion
+    Unterminated { spec: String },
+    /// Format takes different amount of values than there are bindings
+    BindingCount { conversions: usize, bindings: usize },
+    /// Conversion cannot print the type of the bound field
+    BindingType {
+        spec: String,
+        field: &'static str,
+        ctype: CType,
+    },
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::Unknown { spec, kind } => {
+                write!(f, "{:?} is not a {} conversion", spec, kind)
+            }
+            FormatError::Unterminated { spec } => {
+                write!(f, "format ends in the middle of {:?}", spec)
+            }
+            FormatError::BindingCount {
+                conversions,
+                bindings,
+            } => write!(
+                f,
+                "format takes {} values, but {} fields are bound",
+                conversions, bindings
+            ),
+            FormatError::BindingType { spec, field, ctype } => {
+                write!(f, "{:?} cannot print {} of type {}", spec, field, ctype)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+const PRINTF_FLAGS: &str = "-+ #0'";
+const PRINTF_LENGTHS: [&str; 8] = ["hh", "ll", "h", "l", "L", "j", "z", "t"];
+const PRINTF_CONVERSIONS: &str = "diouxXfFeEgGaAcsp";
+const STRFTIME_CONVERSIONS: &str = "aAbBcCdDeFgGhHIjmMnprRStTuUVwWxXyYzZ";
+const DURATION_CONVERSIONS: &str = "HMST";
+
+/// Splits the format into literals and conversions,
+/// checking conversions are valid for the kind
+pub fn parse(format: &str, kind: FormatKind) -> Result<Vec<Piece>, FormatError> {
+    let mut pieces = Vec::new();
+    let mut literal = String::new();
+    let mut rest = format;
+
+    while let Some(start) = rest.find('%') {
+        literal.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if let Some(after) = rest.strip_prefix("%%") {
+            literal.push('%');
+            rest = after;
+            continue;
+        }
+
+        let (conversion, after) = match kind {
+            FormatKind::Printf => parse_printf(rest)?,
+            FormatKind::Strftime | FormatKind::Duration => parse_strftime(rest, kind)?,
+        };
+        if !literal.is_empty() {
+            pieces.push(Piece::Literal(std::mem::take(&mut literal)));
+        }
+        pieces.push(Piece::Conversion(conversion));
+        rest = after;
+    }
+
+    literal.push_str(rest);
+    if !literal.is_empty() {
+        pieces.push(Piece::Literal(literal));
+    }
+
+    Ok(pieces)
+}
+
+/// Text which the format prints as is
+pub fn literals(pieces: &[Piece]) -> String {
+    pieces
+        .iter()
+        .filter_map(|piece| match piece {
+            Piece::Literal(text) => Some(text.as_str()),
+            Piece::Conversion(_) => None,
+        })
+        .collect()
+}
+
+/// Format as it was written, with '%' of the literals escaped again
+fn written(pieces: &[Piece]) -> String {
+    pieces
+        .iter()
+        .map(|piece| match piece {
+            Piece::Literal(text) => text.replace('%', "%%"),
+            Piece::Conversion(conversion) => conversion.spec.clone(),
+        })
+        .collect()
+}
+
+/// Checks there is a binding of matching type for every value format takes.
+/// Whole strftime and duration formats print a single value
+pub fn check_bindings(
+    pieces: &[Piece],
+    kind: FormatKind,
+    bindings: &[Binding],
+) -> Result<(), FormatError> {
+    let conversions: Vec<_> = pieces
+        .iter()
+        .filter_map(|piece| match piece {
+            Piece::Conversion(conversion) => Some(conversion),
+            Piece::Literal(_) => None,
+        })
+        .collect();
+
+    let values = match kind {
+        FormatKind::Printf => conversions.len(),
+        FormatKind::Strftime | FormatKind::Duration => 1,
+    };
+    if values != bindings.len() {
+        return Err(FormatError::BindingCount {
+            conversions: values,
+            bindings: bindings.len(),
+        });
+    }
+
+    let mismatch = |spec: &str, binding: &Binding| FormatError::BindingType {
+        spec: spec.into(),
+        field: binding.field,
+        ctype: binding.ctype,
+    };
+
+    match kind {
+        FormatKind::Printf => {
+            for (conversion, binding) in conversions.iter().zip(bindings) {
+                if !printf_accepts(conversion, binding.ctype) {
+                    return Err(mismatch(&conversion.spec, binding));
+                }
+            }
+        }
+        FormatKind::Strftime => {
+            if bindings[0].ctype != CType::Time {
+                return Err(mismatch(&written(pieces), &bindings[0]));
+            }
+        }
+        FormatKind::Duration => {
+            // Seconds are uint32_t in the generated code
+            let wide = matches!(bindings[0].ctype, CType::I64 | CType::U64);
+            if !is_integer(bindings[0].ctype) || wide {
+                return Err(mismatch(&written(pieces), &bindings[0]));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Types printf conversion prints, once promoted to int or double
+fn printf_accepts(conversion: &Conversion, ctype: CType) -> bool {
+    // long is 32 bits on the device, 64 bit values need "ll" or "j"
+    let wide = matches!(conversion.length.as_str(), "ll" | "j");
+
+    match conversion.conversion {
+        'd' | 'i' if wide => ctype == CType::I64,
+        'd' | 'i' => matches!(ctype, CType::U8 | CType::U16 | CType::I32),
+        'u' | 'o' | 'x' | 'X' if wide => ctype == CType::U64,
+        'u' | 'o' | 'x' | 'X' => matches!(ctype, CType::U8 | CType::U16 | CType::U32),
+        'f' | 'F' | 'e' | 'E' | 'g' | 'G' | 'a' | 'A' => matches!(ctype, CType::F32 | CType::F64),
+        'c' => ctype == CType::U8,
+        's' => ctype == CType::Str,
+        // Pointer
+        _ => false,
+    }
+}
+
+fn is_integer(ctype: CType) -> bool {
+    matches!(
+        ctype,
+        CType::U8 | CType::U16 | CType::I32 | CType::U32 | CType::I64 | CType::U64
+    )
+}
+
+/// Widest text the format may print, given values of the bindings,
+/// texts are measured by the width function, in pixels or bytes.
+/// None if any of the values is not bounded, like a float without a range.
+///
+/// Only integer and %f conversions are sized, the widest of their values is at
+/// an end of the range, provided digits are equally wide, as in firmware fonts.
+/// %e and %g print longer text in between, like 0.000123 against 1,
+/// so they are left to the sample text of the tile
+pub fn worst_case(
+    pieces: &[Piece],
+    kind: FormatKind,
+    bindings: &[Binding],
+    width: &dyn Fn(&str) -> usize,
+) -> Option<String> {
+    let mut values = bindings.iter();
+    let mut text = String::new();
+
+    for piece in pieces {
+        let conversion = match piece {
+            Piece::Literal(literal) => {
+                text.push_str(literal);
+                continue;
+            }
+            Piece::Conversion(conversion) => conversion,
+        };
+
+        let printed = match kind {
+            FormatKind::Printf => {
+                let (min, max) = values.next()?.range()?;
+                let min = printf_value(conversion, min)?;
+                let max = printf_value(conversion, max)?;
+                match width(&min) > width(&max) {
+                    true => min,
+                    false => max,
+                }
+            }
+            FormatKind::Strftime => strftime_value(conversion)?.to_string(),
+            FormatKind::Duration => {
+                // Every conversion prints a part of the same seconds
+                let (_, max) = bindings.first()?.range()?;
+                let hours = format!("{:02}", max.max(0.0) as u64 / 3600);
+                match conversion.conversion {
+                    'H' => hours,
+                    'T' => hours + ":59:59",
+                    _ => "59".into(),
+                }
+            }
+        };
+        text.push_str(&printed);
+    }
+
+    Some(text)
+}
+
+/// Value printed by the integer or %f conversion, the way C does it
+fn printf_value(conversion: &Conversion, value: f64) -> Option<String> {
+    let flags = &conversion.flags;
+    let wide = matches!(conversion.length.as_str(), "ll" | "j");
+    let precision = conversion.precision;
+
+    let (sign, digits) = match conversion.conversion {
+        'd' | 'i' | 'u' | 'o' | 'x' | 'X' => {
+            let value = value.trunc();
+            let negative = value < 0.0 && matches!(conversion.conversion, 'd' | 'i');
+            let magnitude = match conversion.conversion {
+                'd' | 'i' => value.abs() as u64,
+                // Negative values wrap around, as in C
+                _ if wide => value as i64 as u64,
+                _ => value as i64 as u32 as u64,
+            };
+            let mut digits = match conversion.conversion {
+                'o' => format!("{:o}", magnitude),
+                'x' => format!("{:x}", magnitude),
+                'X' => format!("{:X}", magnitude),
+                _ => magnitude.to_string(),
+            };
+            if let Some(precision) = precision {
+                digits = format!("{:0>width$}", digits, width = precision);
+            }
+            if flags.contains('#') && magnitude != 0 {
+                digits = match conversion.conversion {
+                    'o' => format!("0{}", digits),
+                    'x' => format!("0x{}", digits),
+                    'X' => format!("0X{}", digits),
+                    _ => digits,
+                };
+            }
+            (sign(flags, negative), digits)
+        }
+        'f' | 'F' => (
+            sign(flags, value < 0.0),
+            format!("{:.*}", precision.unwrap_or(6), value.abs()),
+        ),
+        // Longest text of the others is not at the ends of the range,
+        // strings and pointers have no range at all
+        _ => return None,
+    };
+
+    let mut printed = format!("{}{}", sign, digits);
+    if conversion.conversion.is_ascii_uppercase() {
+        printed = printed.to_uppercase();
+    }
+
+    let width = conversion.width.unwrap_or(0);
+    let zero_padded = flags.contains('0')
+        && !flags.contains('-')
+        && !(precision.is_some() && "diouxX".contains(conversion.conversion));
+    Some(match (flags.contains('-'), zero_padded) {
+        (true, _) => format!("{:<width$}", printed, width = width),
+        (false, true) => format!(
+            "{}{:0>width$}",
+            sign,
+            &printed[sign.len()..],
+            width = width.saturating_sub(sign.len())
+        ),
+        (false, false) => format!("{:>width$}", printed, width = width),
+    })
+}
+
+fn sign(flags: &str, negative: bool) -> &'static str {
+    match (negative, flags.contains('+'), flags.contains(' ')) {
+        (true, _, _) => "-",
+        (false, true, _) => "+",
+        (false, false, true) => " ",
+        _ => "",
+    }
+}
+
+/// Widest text strftime conversion prints, in the C locale.
+/// None if it depends on the settings of the device
+fn strftime_value(conversion: &Conversion) -> Option<&'static str> {
+    let value = match conversion.conversion {
+        'a' => "Wed",
+        'A' => "Wednesday",
+        'b' | 'h' => "May",
+        'B' => "September",
+        'C' | 'g' | 'y' => "99",
+        'd' | 'e' => "31",
+        'H' => "23",
+        'I' | 'm' => "12",
+        'M' => "59",
+        // Leap second
+        'S' => "60",
+        'U' | 'V' | 'W' => "53",
+        'D' | 'x' => "12/31/99",
+        'F' => "2099-12-31",
+        'G' | 'Y' => "2099",
+        'j' => "366",
+        // Whitespace, a character each
+        'n' | 't' => " ",
+        'p' => "PM",
+        'r' => "11:59:59 PM",
+        'R' => "23:59",
+        'T' | 'X' => "23:59:59",
+        'u' | 'w' => "7",
+        'z' => "+0000",
+        // Date and time of the locale, name of the time zone, any length
+        'c' | 'Z' => return None,
+        _ => "",
+    };
+    Some(value)
+}
+
+/// Turns duration format into printf one, printing given amount of seconds.
+/// Returns the format and its arguments
+pub fn duration_to_printf(pieces: &[Piece], seconds: &str) -> (String, Vec<String>) {
+    let mut format = String::new();
+    let mut args = Vec::new();
+
+    for piece in pieces {
+        match piece {
+            Piece::Literal(text) => format.push_str(&text.replace('%', "%%")),
+            Piece::Conversion(conversion) => {
+                let parts: &[&str] = match conversion.conversion {
+                    'H' => &["{} / 3600"],
+                    'M' => &["{} / 60 % 60"],
+                    'S' => &["{} % 60"],
+                    // Only T is left after parsing
+                    _ => &["{} / 3600", "{} / 60 % 60", "{} % 60"],
+                };
+                let formats: Vec<_> = parts.iter().map(|_| "%02u").collect();
+                format.push_str(&formats.join(":"));
+                args.extend(parts.iter().map(|part| part.replace("{}", seconds)));
+            }
+        }
+    }
+
+    (format, args)
+}
+
+fn parse_printf(spec: &str) -> Result<(Conversion, &str), FormatError> {
+    let unterminated = || FormatError::Unterminated { spec: spec.into() };
+    // Skip the '%'
+    let mut rest = &spec[1..];
+
+    let flags_len = rest.len() - rest.trim_start_matches(|c| PRINTF_FLAGS.contains(c)).len();
+    let flags = rest[..flags_len].to_string();
+    rest = &rest[flags_len..];
+
+    let (width, after) = number(rest);
+    rest = after;
+
+    let precision = match rest.strip_prefix('.') {
+        Some(after) => {
+            let (precision, after) = number(after);
+            rest = after;
+            // "%.f" means precision 0
+            Some(precision.unwrap_or_default())
+        }
+        None => None,
+    };
+
+    let length = PRINTF_LENGTHS
+        .iter()
+        .find(|length| rest.starts_with(*length))
+        .copied()
+        .unwrap_or_default();
+    rest = &rest[length.len()..];
+
+    let conversion = rest.chars().next().ok_or_else(unterminated)?;
+    rest = &rest[conversion.len_utf8()..];
+    let written = &spec[..spec.len() - rest.len()];
+
+    if !PRINTF_CONVERSIONS.contains(conversion) {
+        return Err(FormatError::Unknown {
+            spec: written.into(),
+            kind: FormatKind::Printf,
+        });
+    }
+
+    Ok((
+        Conversion {
+            spec: written.into(),
+            flags,
+            width,
+            precision,
+            length: length.into(),
+            conversion,
+        },
+        rest,
+    ))
+}
+
+fn parse_strftime(spec: &str, kind: FormatKind) -> Result<(Conversion, &str), FormatError> {
+    let mut rest = &spec[1..];
+
+    let length = match kind {
+        FormatKind::Strftime if rest.starts_with(['E', 'O']) => &rest[..1],
+        _ => "",
+    };
+    rest = &rest[length.len()..];
+
+    let conversion = rest
+        .chars()
+        .next()
+        .ok_or_else(|| FormatError::Unterminated { spec: spec.into() })?;
+    rest = &rest[conversion.len_utf8()..];
+    let written = &spec[..spec.len() - rest.len()];
+
+    let conversions = match kind {
+        FormatKind::Duration => DURATION_CONVERSIONS,
+        _ => STRFTIME_CONVERSIONS,
+    };
+    if !conversions.contains(conversion) {
+        return Err(FormatError::Unknown {
+            spec: written.into(),
+            kind,
+        });
+    }
+
+    Ok((
+        Conversion {
+            spec: written.into(),
+            flags: String::new(),
+            width: None,
+            precision: None,
+            length: length.into(),
+            conversion,
+        },
+        rest,
+    ))
+}
+
+/// Leading decimal number of the text, and the rest of it
+fn number(text: &str) -> (Option<usize>, &str) {
+    let digits = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+
+    (text[..digits].parse().ok(), &text[digits..])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn printf_conversions_are_split_from_literals() {
+        let pieces = parse("%07.2f[m] 100%%", FormatKind::Printf).unwrap();
+
+        assert_eq!(
+            pieces,
+            vec![
+                Piece::Conversion(Conversion {
+                    spec: "%07.2f".into(),
+                    flags: "0".into(),
+                    width: Some(7),
+                    precision: Some(2),
+                    length: "".into(),
+                    conversion: 'f',
+                }),
+                Piece::Literal("[m] 100%".into()),
+            ]
+        );
+        assert_eq!(literals(&pieces), "[m] 100%");
+    }
+
+    #[test]
+    fn conversions_must_match_the_kind() {
+        assert_eq!(
+            parse("%d/%m/%y", FormatKind::Printf),
+            Err(FormatError::Unknown {
+                spec: "%m".into(),
+                kind: FormatKind::Printf
+            })
+        );
+        assert!(parse("%d/%m/%y", FormatKind::Strftime).is_ok());
+        assert_eq!(
+            parse("%d days", FormatKind::Duration),
+            Err(FormatError::Unknown {
+                spec: "%d".into(),
+                kind: FormatKind::Duration
+            })
+        );
+        assert_eq!(
+            parse("GPS %5.", FormatKind::Printf),
+            Err(FormatError::Unterminated { spec: "%5.".into() })
+        );
+        assert_eq!(
+            FormatError::Unknown {
+                spec: "%T".into(),
+                kind: FormatKind::Printf
+            }
+            .to_string(),
+            r#""%T" is not a printf conversion"#
+        );
+    }
+
+    #[test]
+    fn duration_becomes_printf_of_seconds() {
+        let pieces = parse("%T lap", FormatKind::Duration).unwrap();
+
+        assert_eq!(
+            duration_to_printf(&pieces, "s"),
+            (
+                "%02u:%02u:%02u lap".to_string(),
+                vec!["s / 3600".into(), "s / 60 % 60".into(), "s % 60".into()]
+            )
+        );
+    }
+
+    #[test]
+    fn bindings_match_printf_conversions() {
+        let pieces = parse("%d / %d", FormatKind::Printf).unwrap();
+        let in_view = Binding {
+            field: "gps.in_view",
+            ctype: CType::U8,
+            range: None,
+        };
+        let tracked = Binding {
+            field: "gps.tracked",
+            ctype: CType::U8,
+            range: None,
+        };
+
+        assert_eq!(
+            check_bindings(&pieces, FormatKind::Printf, &[in_view, tracked]),
+            Ok(())
+        );
+        assert_eq!(
+            check_bindings(&pieces, FormatKind::Printf, &[in_view]),
+            Err(FormatError::BindingCount {
+                conversions: 2,
+                bindings: 1
+            })
+        );
+
+        let pieces = parse("%5.2f[*C]", FormatKind::Printf).unwrap();
+        let temperature = Binding {
+            field: "temperature",
+            ctype: CType::I32,
+            range: None,
+        };
+        assert_eq!(
+            check_bindings(&pieces, FormatKind::Printf, &[temperature])
+                .unwrap_err()
+                .to_string(),
+            r#""%5.2f" cannot print temperature of type int32_t"#
+        );
+
+        let pieces = parse("%lld", FormatKind::Printf).unwrap();
+        let steps = Binding {
+            field: "steps",
+            ctype: CType::I64,
+            range: None,
+        };
+        assert_eq!(
+            check_bindings(&pieces, FormatKind::Printf, &[steps]),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn duration_binding_fits_in_32_bits() {
+        let pieces = parse("%T [h]", FormatKind::Duration).unwrap();
+        let elapsed = |ctype| Binding {
+            field: "elapsed",
+            ctype,
+            range: None,
+        };
+
+        assert_eq!(
+            check_bindings(&pieces, FormatKind::Duration, &[elapsed(CType::U32)]),
+            Ok(())
+        );
+        assert_eq!(
+            check_bindings(&pieces, FormatKind::Duration, &[elapsed(CType::U64)])
+                .unwrap_err()
+                .to_string(),
+            r#""%T [h]" cannot print elapsed of type uint64_t"#
+        );
+    }
+
+    #[test]
+    fn worst_case_is_the_longest_value_of_the_range() {
+        let worst = |format, kind, ctype, range| {
+            let pieces = parse(format, kind).unwrap();
+            let binding = Binding {
+                field: "value",
+                ctype,
+                range,
+            };
+            worst_case(&pieces, kind, &[binding], &|text: &str| text.len())
+        };
+
+        let temperature = Some((-123.45, 99.0));
+        assert_eq!(
+            worst("%5.2f[*C]", FormatKind::Printf, CType::F32, temperature),
+            Some("-123.45[*C]".into())
+        );
+        assert_eq!(
+            worst(
+                "%07.2f",
+                FormatKind::Printf,
+                CType::F32,
+                Some((0.0, 8848.94))
+            ),
+            Some("8848.94".into())
+        );
+        assert_eq!(
+            worst("%+08.1f", FormatKind::Printf, CType::F32, Some((0.0, 9.5))),
+            Some("+00009.5".into())
+        );
+        // Float needs the range, integer type has one
+        assert_eq!(worst("%f", FormatKind::Printf, CType::F32, None), None);
+        assert_eq!(
+            worst("in view: %d", FormatKind::Printf, CType::U8, None),
+            Some("in view: 255".into())
+        );
+        assert_eq!(
+            worst("%#x", FormatKind::Printf, CType::U16, None),
+            Some("0xffff".into())
+        );
+        // 0.000123 is longer than both ends
+        assert_eq!(
+            worst("%g", FormatKind::Printf, CType::F64, Some((0.0, 1.0))),
+            None
+        );
+        assert_eq!(
+            worst("%e", FormatKind::Printf, CType::F64, Some((0.0, 150.0))),
+            None
+        );
+
+        assert_eq!(
+            worst("%d/%m/%y", FormatKind::Strftime, CType::Time, None),
+            Some("31/12/99".into())
+        );
+        assert_eq!(
+            worst("%H:%M %Z", FormatKind::Strftime, CType::Time, None),
+            None
+        );
+        assert_eq!(worst("%c", FormatKind::Strftime, CType::Time, None), None);
+        assert_eq!(
+            worst("%T", FormatKind::Duration, CType::U32, Some((0.0, 36000.0))),
+            Some("10:59:59".into())
+        );
+    }
+
+    #[test]
+    fn worst_case_is_the_widest_in_pixels() {
+        let pieces = parse("%d", FormatKind::Printf).unwrap();
+        let binding = Binding {
+            field: "value",
+            ctype: CType::I32,
+            range: Some((-11.0, 85.0)),
+        };
+        let bytes = |text: &str| text.len();
+        // "-11" has more characters, but "85" is wider in proportional font
+        let proportional = |text: &str| -> usize {
+            text.chars()
+                .map(|c| if c == '-' || c == '1' { 2 } else { 8 })
+                .sum()
+        };
+
+        assert_eq!(
+            worst_case(&pieces, FormatKind::Printf, &[binding], &bytes),
+            Some("-11".into())
+        );
+        assert_eq!(
+            worst_case(&pieces, FormatKind::Printf, &[binding], &proportional),
+            Some("85".into())
+        );
+    }
+}
@@ -1,6 +1,14 @@
+use std::path::Path;
+
 use crate::{
     bc_render::render_to_bc,
-    common::{h_layout, h_line, h_split, invalidate_dimensions, tile, v_layout, v_line, Dimension},
+    common::{
+        h_layout, h_line, h_split, h_split_n, invalidate_dimensions, tile, v_layout, v_line,
+        v_list, v_split, v_split_n, write_to_file, Edges, HAlign, Highlight, Node, Size,
+        TextOverflow, VAlign,
+    },
+    display::DisplayProfile,
+    format::CType,
     sixtyfps_render::render_to_60fps,
 };
 
@@ -10,83 +18,271 @@ use crate::{
 
 mod bc_render;
 mod common;
-mod sixtyfps_render;
+mod display;
+mod fonts;
+mod format;
 mod mocks_sharp_mip_2in7;
 mod mocks_waveshare2in9;
+mod sixtyfps_render;
 
 fn main() {
-    bc_test_page();
+    // Display and fonts of the firmware may be given,
+    // cargo run -- sharp ../bike_computer_esp32/fonts
+    let mut args = std::env::args().skip(1);
+    let display = match args.next().as_deref() {
+        Some("sharp") => DisplayProfile::sharp_mip_2in7(),
+        Some("waveshare") | None => DisplayProfile::waveshare_2in9(),
+        Some(other) => {
+            eprintln!("Unknown display {}, use sharp or waveshare", other);
+            return;
+        }
+    };
+    let display = match args.next() {
+        Some(dir) => match display.with_fonts_from(Path::new(&dir)) {
+            Ok(display) => display,
+            Err(err) => {
+                eprintln!("Cannot load fonts, {}", err);
+                return;
+            }
+        },
+        None => display,
+    };
+
+    let mut gui = bc_test_page();
+    if generate(&mut gui, &display, "Status") {
+        write_to_file(&render_to_60fps(&gui, &display), "ui/main.60");
+    }
+    generate(&mut bc_running_page(), &display, "Running");
+    // Font of the list is not fitted by the layout, it gets the smallest one
+    if let Some(font) = display.layout.fonts.smallest() {
+        generate(
+            &mut bc_select_activity_page(font.size),
+            &display,
+            "SelectActivity",
+        );
+    }
 
     // let main_window = MainWindow::new();
     // main_window.run();
 }
 
+/// Lays out the page and writes its view to bc/,
+/// false if the page cannot be laid out on the display
+fn generate(gui: &mut Node, display: &DisplayProfile, page: &str) -> bool {
+    match invalidate_dimensions(gui, display) {
+        Ok(report) => {
+            for missing in &report.missing_glyphs {
+                eprintln!("Warning: {}", missing);
+            }
+            for overflowed in &report.overflowed {
+                eprintln!("Warning: {}", overflowed);
+            }
+        }
+        Err(err) => {
+            eprintln!("Cannot lay out {} page, {}", page, err);
+            return false;
+        }
+    }
+
+    let view = render_to_bc(gui, display, page);
+    println!("{}: {}", view.class, view.refresh);
+    view.write_to_dir("bc");
+    true
+}
+
+/// Clock, GPS fix and date, on top of every page
+fn status_bar() -> Node {
+    h_layout([
+        tile("21:37")
+            .with_strftime("%T")
+            .bind("clock", CType::Time)
+            .font_group("status"),
+        tile("GPS 3D")
+            .with_format("GPS %1d")
+            .bind("gps.fix", CType::U8)
+            .with_range(0.0, 3.0)
+            .font_group("status"),
+        tile("02/09/21")
+            .with_strftime("%d/%m/%y")
+            .bind("clock", CType::Time)
+            .font_group("status"),
+    ])
+    .separated()
+    .with_height(Size::Percent(0.1))
+}
+
 /// Generate a mockup by declaring a GUI tree.
 ///
 /// Having a tree, call invalidate_dimensions,
 /// to automatically calculate widths and heights
 /// of each element.
 ///
-/// Call render_to_60fps to generate .60 markup
-/// file allowing to quick peek on how mockup looks like.
+/// Call render_to_60fps to generate .60 markup,
+/// written to ui/main.60 to quick peek on how mockup looks like.
 ///
 /// Call render_to_bc to generate C++ code so you don't
 /// have to write it anymore!
-fn bc_test_page() {
-    let status_bar = h_layout([
-        tile("21:37").with_format("%T"),
-        v_line(),
-        tile("GPS 3D").with_format("GPS %1d"),
-        v_line(),
-        tile("02/09/21").with_format("%d/%m/%y"),
-    ]);
-
+fn bc_test_page() -> Node {
     let welcome_page = v_layout([
         h_line(),
         v_line(),
         h_layout([
             v_layout([
-                tile("02/09/21").with_format("%d/%m/%y"),
-                tile("19:34:20").with_format("%T"),
+                tile("02/09/21")
+                    .with_strftime("%d/%m/%y")
+                    .bind("clock", CType::Time),
+                tile("19:34:20")
+                    .with_strftime("%T")
+                    .bind("clock", CType::Time),
             ]),
             v_layout([
-                tile("in view / tracked"),
-                tile("13 / 11").with_format("%d / %d").with_font_size(19),
+                tile("in view / tracked").with_align(HAlign::Left, VAlign::Bottom),
+                // Counts cut in half would be misread
+                tile("13 / 11")
+                    .with_format("%d / %d")
+                    .bind("gps.in_view", CType::U8)
+                    .bind("gps.tracked", CType::U8)
+                    .with_source("gps")
+                    .with_align(HAlign::Left, VAlign::Top)
+                    .with_overflow(TextOverflow::Fail),
             ]),
         ]),
-        // Current implementation of invalidate_dimensions
-        // makes {h,v}_line split tiles defined after them
-        // so they 'stick' to the bottom
+        // h_line goes on the boundary with the widgets above,
+        // v_line splits the h_layout which follows it
         h_line(),
         v_line(),
         h_layout([
             v_layout([
-                tile("23.19[*C]").with_format("%5.2f[*C]"),
-                tile("133.94[m]").with_format("%5.2f[m]"),
-            ]),
-            v_layout([
-                tile("Hit button below"),
-                tile("to calculate your"),
-                tile("BMI"),
+                tile("23.19[*C]")
+                    .with_format("%5.2f[*C]")
+                    .bind("temperature", CType::F32)
+                    .with_range(-40.0, 85.0)
+                    .with_align(HAlign::Right, VAlign::Baseline),
+                tile("133.94[m]")
+                    .with_format("%5.2f[m]")
+                    .bind("altitude", CType::F32)
+                    .with_range(-500.0, 9000.0)
+                    .with_align(HAlign::Right, VAlign::Baseline),
             ]),
+            tile("Hit button below to calculate your BMI")
+                .wrapped()
+                .with_margin(Edges::symmetric(0, 2)),
         ]),
     ]);
 
-    let mut gui = h_split(status_bar, 0.101, welcome_page);
+    v_layout([status_bar(), welcome_page])
+}
 
-    // Dimensions of BC display
-    let d = Dimension {
-        x: 0,
-        y: 0,
-        width: 296,
-        height: 128,
+/// Values of the run, label above each of them
+fn bc_running_page() -> Node {
+    let value = |label, value: Node| {
+        v_layout([
+            tile(label).with_align(HAlign::Left, VAlign::Bottom),
+            value.font_group("values"),
+        ])
     };
 
-    invalidate_dimensions(&mut gui, &d);
+    let running_page = v_split_n([
+        (
+            h_split_n([
+                (
+                    value(
+                        "pace",
+                        tile("5.20")
+                            .with_format("%.2f")
+                            .bind("pace", CType::F32)
+                            .with_range(0.0, 60.0),
+                    ),
+                    0.5,
+                ),
+                (
+                    value(
+                        "distance",
+                        tile("10.20")
+                            .with_format("%.2f")
+                            .bind("distance", CType::F64)
+                            .with_range(0.0, 999.0),
+                    ),
+                    0.5,
+                ),
+            ]),
+            0.5,
+        ),
+        (
+            h_split_n([
+                (
+                    value(
+                        "lap time",
+                        tile("01:12:20")
+                            .with_duration("%T")
+                            .bind("lap.elapsed", CType::U32)
+                            .with_range(0.0, 86399.0),
+                    ),
+                    0.5,
+                ),
+                (
+                    value(
+                        "ascent",
+                        tile("123")
+                            .with_format("%d")
+                            .bind("ascent", CType::I32)
+                            .with_range(-999.0, 9999.0),
+                    ),
+                    0.5,
+                ),
+            ]),
+            0.5,
+        ),
+    ]);
+
+    v_layout([
+        status_bar(),
+        h_line(),
+        h_layout([
+            tile("cadence")
+                .with_width(Size::Pixels(96))
+                .with_padding(Edges::symmetric(1, 4)),
+            tile("158")
+                .with_format("%d")
+                .bind("cadence", CType::U16)
+                .with_range(0.0, 255.0),
+        ])
+        .with_height(Size::Weight(1)),
+        h_line(),
+        running_page.with_height(Size::Weight(4)),
+    ])
+}
 
-    render_to_60fps(&gui, &d);
+/// Activity to start, picked from the list
+fn bc_select_activity_page(list_font: usize) -> Node {
+    let select_page = h_split(
+        tile("Running")
+            .dynamic("activity")
+            .with_overflow(TextOverflow::Clip),
+        0.2,
+        v_layout([
+            h_line(),
+            v_line(),
+            v_split(
+                tile("Activity"),
+                0.4,
+                v_list([
+                    "Running",
+                    "Cycling",
+                    "Hiking",
+                    "Ind. Cycling",
+                    "Yoga",
+                    "Swimming",
+                ])
+                .with_visible(4)
+                .with_font_size(list_font)
+                .with_selected(0, Highlight::Frame)
+                .with_overflow(TextOverflow::Ellipsize),
+            ),
+        ]),
+    );
 
-    render_to_bc(&gui, &d);
+    v_layout([status_bar(), h_line(), select_page])
 }
 
 /// Rest of mockups are in form of tests, so you can quickly
@@ -96,13 +292,31 @@ mod test {
     use crate::{
         bc_render::render_to_bc,
         common::{
-            h_layout, h_line, h_split, invalidate_dimensions, tile, v_layout, v_line, v_list,
-            Dimension,
+            self, assert_leaves_cover, h_layout, h_line, h_split, h_split_n, invalidate_dimensions,
+            leaf_dimensions, tile, v_layout, v_line, v_list, v_split, v_split_n, Dimension, Edges,
+            HAlign, Highlight, LayoutConfig, LayoutError, Node, Size, TextOverflow, VAlign,
         },
+        display::DisplayProfile,
+        fonts::{Font, FontSet},
+        format::CType,
         sixtyfps_render::render_to_60fps,
     };
 
-    use super::*;
+    /// Display of the area, with the default layout
+    fn display(d: Dimension) -> DisplayProfile {
+        display_with(d, LayoutConfig::default())
+    }
+
+    /// Display of the given area and layout, the rest as on Sharp
+    fn display_with(dim: Dimension, layout: LayoutConfig) -> DisplayProfile {
+        DisplayProfile {
+            name: "Custom",
+            dim,
+            layout,
+            ..DisplayProfile::sharp_mip_2in7()
+        }
+    }
+
     #[test]
     fn sample_gui() {
         let mut gui = v_layout([
@@ -116,16 +330,13 @@ mod test {
             ]),
         ]);
 
-        let d = Dimension {
-            x: 0,
-            y: 0,
-            width: 296,
-            height: 128,
-        };
+        let d = DisplayProfile::waveshare_2in9().dim;
+
+        invalidate_dimensions(&mut gui, &display(d)).unwrap();
 
-        invalidate_dimensions(&mut gui, &d);
+        assert_leaves_cover(&gui, &d);
 
-        render_to_60fps(&gui, &d);
+        render_to_60fps(&gui, &display(d));
     }
 
     #[test]
@@ -140,16 +351,13 @@ mod test {
             h_layout([tile("C"), tile("D")]),
         ]);
 
-        let d = Dimension {
-            x: 0,
-            y: 0,
-            width: 296,
-            height: 128,
-        };
+        let d = DisplayProfile::waveshare_2in9().dim;
 
-        invalidate_dimensions(&mut gui, &d);
+        invalidate_dimensions(&mut gui, &display(d)).unwrap();
 
-        render_to_60fps(&gui, &d);
+        assert_leaves_cover(&gui, &d);
+
+        render_to_60fps(&gui, &display(d));
     }
 
     #[test]
@@ -172,17 +380,1471 @@ mod test {
             v_layout([tile("C"), tile("D")]),
         ]);
 
+        let d = DisplayProfile::waveshare_2in9().dim;
+
+        invalidate_dimensions(&mut gui, &display(d)).unwrap();
+
+        assert_leaves_cover(&gui, &d);
+
+        render_to_60fps(&gui, &display(d));
+    }
+
+    #[test]
+    fn fixed_status_bar_and_weighted_body() {
+        // 24px status bar on top, body gets the rest,
+        // split 1:2 between label and value
+        let mut gui = v_layout([
+            h_layout([tile("21:37"), tile("GPS 3D")]).with_height(Size::Pixels(24)),
+            h_layout([
+                tile("pace").with_width(Size::Weight(1)),
+                tile("10.20").with_width(Size::Weight(2)),
+            ]),
+        ]);
+
         let d = Dimension {
             x: 0,
             y: 0,
-            width: 296,
+            width: 300,
             height: 128,
         };
 
-        invalidate_dimensions(&mut gui, &d);
+        invalidate_dimensions(&mut gui, &display(d)).unwrap();
+
+        assert_leaves_cover(&gui, &d);
+
+        let tiles = match &gui {
+            Node::V(layout) => match &layout.nodes[1] {
+                Node::H(body) => &body.nodes,
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+
+        match (&tiles[0], &tiles[1]) {
+            (Node::Tile(label), Node::Tile(value)) => {
+                assert_eq!((label.dim.x, label.dim.y), (0, 24));
+                assert_eq!((label.dim.width, label.dim.height), (100, 104));
+                assert_eq!((value.dim.x, value.dim.width), (100, 200));
+            }
+            _ => unreachable!(),
+        }
+
+        render_to_60fps(&gui, &display(d));
+    }
+
+    #[test]
+    fn percent_and_fixed_sizes_in_both_axes() {
+        let mut gui = h_layout([
+            tile("A").with_width(Size::Percent(0.25)),
+            tile("B")
+                .with_width(Size::Pixels(50))
+                .with_height(Size::Pixels(32)),
+            tile("C"),
+        ]);
+
+        let d = DisplayProfile::waveshare_2in9().dim;
+
+        invalidate_dimensions(&mut gui, &display(d)).unwrap();
+
+        let dims: Vec<_> = match &gui {
+            Node::H(layout) => layout
+                .nodes
+                .iter()
+                .map(|node| match node {
+                    Node::Tile(tile) => (tile.dim.x, tile.dim.width, tile.dim.height),
+                    _ => unreachable!(),
+                })
+                .collect(),
+            _ => unreachable!(),
+        };
+
+        assert_eq!(dims, vec![(0, 74, 128), (74, 50, 32), (124, 172, 128)]);
+    }
+    #[test]
+    fn v_split_puts_label_column_next_to_list() {
+        // 30% label column | 70% list, v_line sticks to the split
+        let mut gui = v_layout([
+            v_line(),
+            v_split(
+                tile("Activity"),
+                0.3,
+                v_list(["Running", "Cycling"]).with_font_size(24),
+            ),
+        ]);
+
+        let d = Dimension {
+            x: 0,
+            y: 0,
+            width: 400,
+            height: 240,
+        };
+
+        invalidate_dimensions(&mut gui, &display(d)).unwrap();
+
+        assert_leaves_cover(&gui, &d);
+
+        let nodes = match &gui {
+            Node::V(layout) => &layout.nodes,
+            _ => unreachable!(),
+        };
+
+        match (&nodes[0], &nodes[1]) {
+            (Node::VerticalLine(line), Node::VV(split)) => {
+                assert_eq!(line.dim.x, 120);
+                match (split.first.as_ref(), split.second.as_ref()) {
+                    (Node::Tile(label), Node::VListWidget(list)) => {
+                        assert_eq!((label.dim.x, label.dim.width), (0, 120));
+                        assert_eq!((list.dim.x, list.dim.width), (120, 280));
+                        assert_eq!(list.dim.height, 240);
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            _ => unreachable!(),
+        }
+
+        render_to_60fps(&gui, &display(d));
+        render_to_bc(&gui, &display(d), "Sample");
+    }
+
+    #[test]
+    fn n_way_splits() {
+        let mut gui = h_split_n([
+            (
+                v_split_n([(tile("A"), 0.25), (tile("B"), 0.5), (tile("C"), 0.25)]),
+                0.5,
+            ),
+            (tile("D"), 0.5),
+        ]);
+
+        let d = DisplayProfile::waveshare_2in9().dim;
+
+        invalidate_dimensions(&mut gui, &display(d)).unwrap();
+
+        assert_leaves_cover(&gui, &d);
+
+        let nodes = match &gui {
+            Node::V(layout) => &layout.nodes,
+            _ => unreachable!(),
+        };
+
+        let row: Vec<_> = match &nodes[0] {
+            Node::H(layout) => layout
+                .nodes
+                .iter()
+                .map(|node| match node {
+                    Node::Tile(tile) => (tile.dim.x, tile.dim.width, tile.dim.height),
+                    _ => unreachable!(),
+                })
+                .collect(),
+            _ => unreachable!(),
+        };
+        assert_eq!(row, vec![(0, 74, 64), (74, 148, 64), (222, 74, 64)]);
+
+        match &nodes[1] {
+            Node::Tile(tile) => assert_eq!((tile.dim.y, tile.dim.height), (64, 64)),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn remainder_pixels_go_to_first_children() {
+        // 400 / 3 and 240 / 7 do not divide evenly
+        let mut gui = h_layout([
+            tile("A"),
+            tile("B"),
+            v_layout([
+                tile("1"),
+                tile("2"),
+                tile("3"),
+                tile("4"),
+                tile("5"),
+                tile("6"),
+                tile("7"),
+            ]),
+        ]);
+
+        let d = Dimension {
+            x: 0,
+            y: 0,
+            width: 400,
+            height: 240,
+        };
+
+        invalidate_dimensions(&mut gui, &display(d)).unwrap();
+
+        assert_leaves_cover(&gui, &d);
+
+        let nodes = match &gui {
+            Node::H(layout) => &layout.nodes,
+            _ => unreachable!(),
+        };
+
+        let widths: Vec<_> = leaf_dimensions(&gui).iter().map(|d| d.width).collect();
+        assert_eq!(&widths[..3], &[134, 133, 133]);
+
+        let heights: Vec<_> = leaf_dimensions(&nodes[2])
+            .iter()
+            .map(|d| d.height)
+            .collect();
+        assert_eq!(heights, vec![35, 35, 34, 34, 34, 34, 34]);
+    }
+
+    #[test]
+    fn broken_layouts_report_offending_node() {
+        let d = DisplayProfile::waveshare_2in9().dim;
+
+        // Only lines, nothing to split the area among
+        let mut gui = v_layout([tile("A"), h_layout([v_line(), v_line()])]);
+        assert_eq!(
+            invalidate_dimensions(&mut gui, &display(d)),
+            Err(LayoutError::Empty {
+                path: "root/V[1]/H".into()
+            })
+        );
+
+        // Font does not fit in the half of the status bar
+        let mut gui = h_split(
+            h_layout([
+                tile("21:37"),
+                v_layout([tile("Paused").with_font_size(42), tile("")]),
+            ]),
+            0.3,
+            tile("body"),
+        );
+        let err = invalidate_dimensions(&mut gui, &display(d)).unwrap_err();
+        assert_eq!(
+            err,
+            LayoutError::FontTooBig {
+                path: r#"root/HH.first/H[1]/V[0]/Tile("Paused")"#.into(),
+                font_size: 42,
+                height: 17,
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            r#"root/HH.first/H[1]/V[0]/Tile("Paused"): font 42 does not fit in the height of 17px"#
+        );
+
+        // Line margins are wider than the area
+        let mut gui = h_layout([
+            tile("A").with_width(Size::Pixels(286)),
+            v_layout([h_line(), tile("")]),
+        ]);
+        assert_eq!(
+            invalidate_dimensions(&mut gui, &display(d)),
+            Err(LayoutError::TooSmall {
+                path: "root/H[1]/V[0]/HorizontalLine".into(),
+                needed: 26,
+                available: 10,
+            })
+        );
+
+        // Fixed sizes need more than display has
+        let mut gui = v_layout([
+            tile("A").with_height(Size::Pixels(100)),
+            tile("B").with_height(Size::Percent(0.5)),
+        ]);
+        assert_eq!(
+            invalidate_dimensions(&mut gui, &display(d)),
+            Err(LayoutError::Overflow {
+                path: "root/V".into(),
+                needed: 164,
+                available: 128,
+            })
+        );
+
+        // Nothing to share the space left among
+        let mut gui = v_layout([tile("A").with_height(Size::Weight(0))]);
+        let err = invalidate_dimensions(&mut gui, &display(d)).unwrap_err();
+        assert_eq!(
+            err,
+            LayoutError::ZeroWeights {
+                path: "root/V".into()
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "root/V: weights of the children add up to 0"
+        );
+
+        let mut gui = v_layout([tile("A"), v_list(["Resume"]).with_font_size(16)]);
+        assert_eq!(
+            invalidate_dimensions(&mut gui, &display(d)),
+            Err(LayoutError::UnknownFont {
+                path: r#"root/V[1]/VListWidget[0]/Tile("Resume")"#.into(),
+                font_size: 16,
+            })
+        );
+    }
+
+    #[test]
+    fn separators_sit_on_boundaries() {
+        // V:
+        //   H: A | B | C  (1:1:2, separated)
+        //   ------------
+        //   VV: D |   E   (v_line attached to the 30% split)
+        let mut gui = v_layout([
+            h_layout([tile("A"), tile("B"), tile("C").with_width(Size::Weight(2))]).separated(),
+            h_line(),
+            v_line(),
+            v_split(tile("D"), 0.3, tile("E")),
+        ]);
+
+        let d = Dimension {
+            x: 0,
+            y: 0,
+            width: 400,
+            height: 240,
+        };
+
+        invalidate_dimensions(&mut gui, &display(d)).unwrap();
+
+        assert_leaves_cover(&gui, &d);
+
+        let nodes = match &gui {
+            Node::V(layout) => &layout.nodes,
+            _ => unreachable!(),
+        };
+
+        let columns: Vec<_> = match &nodes[0] {
+            Node::H(layout) => layout.nodes.iter().map(|node| node.dim().x).collect(),
+            _ => unreachable!(),
+        };
+        // A, line, B, line, C
+        assert_eq!(columns, vec![0, 100, 100, 200, 200]);
+
+        assert_eq!(nodes[1].dim().y, 120);
+        assert_eq!(nodes[2].dim().x, 120);
+        assert_eq!(nodes[2].dim().y, 123);
+        assert_eq!(nodes[3].dim().y, 120);
+    }
+
+    #[test]
+    fn trailing_separators_stay_inside_the_layout() {
+        let mut gui = h_layout([v_layout([tile("A"), h_line()]), tile("B"), v_line()]);
+
+        let d = Dimension {
+            x: 0,
+            y: 0,
+            width: 400,
+            height: 240,
+        };
+
+        invalidate_dimensions(&mut gui, &display(d)).unwrap();
+
+        let nodes = match &gui {
+            Node::H(layout) => &layout.nodes,
+            _ => unreachable!(),
+        };
+        match &nodes[0] {
+            Node::V(layout) => assert_eq!(layout.nodes[1].dim().y, 239),
+            _ => unreachable!(),
+        }
+        assert_eq!(nodes[2].dim().x, 399);
+    }
+
+    #[test]
+    fn separators_ignore_margins_of_the_neighbours() {
+        let mut gui = v_layout([
+            tile("A"),
+            h_line(),
+            tile("B").with_margin(Edges::all(10)),
+            v_line(),
+            h_layout([tile("C"), tile("D")]).with_margin(Edges::all(10)),
+        ]);
+
+        let d = Dimension {
+            x: 0,
+            y: 0,
+            width: 400,
+            height: 240,
+        };
+
+        invalidate_dimensions(&mut gui, &display(d)).unwrap();
+
+        let nodes = match &gui {
+            Node::V(layout) => &layout.nodes,
+            _ => unreachable!(),
+        };
+        // Rows are 80px high, line is on the boundary, not inside the margin of B
+        assert_eq!(nodes[1].dim().y, 80);
+
+        // Line spans the whole row of the layout, margin of the line aside
+        let line = nodes[3].dim();
+        let margin = LayoutConfig::default().v_line_margin;
+        assert_eq!(line.x, 200);
+        assert_eq!(
+            (line.y, line.height),
+            (160 + margin.top, 80 - margin.top - margin.bottom)
+        );
+    }
+
+    #[test]
+    fn line_across_a_tile_has_nothing_to_separate() {
+        let mut gui = v_layout([v_line(), tile("A")]);
+
+        let d = DisplayProfile::waveshare_2in9().dim;
+
+        assert_eq!(
+            invalidate_dimensions(&mut gui, &display(d)),
+            Err(LayoutError::NothingToSeparate {
+                path: "root/V[0]/VerticalLine".into()
+            })
+        );
+    }
+
+    #[test]
+    fn margins_and_paddings_override_display_defaults() {
+        let config = LayoutConfig {
+            padding: Edges::all(2),
+            h_line_margin: Edges::symmetric(0, 5),
+            ..Default::default()
+        };
+
+        let mut gui = v_layout([
+            tile("A").with_margin(Edges::all(4)),
+            h_line(),
+            tile("B").with_padding(Edges::symmetric(1, 10)),
+            h_line().with_margin(Edges::default()),
+            tile("C").dynamic("c"),
+        ])
+        .with_padding(Edges::all(1));
+
+        let d = Dimension {
+            x: 0,
+            y: 0,
+            width: 300,
+            height: 122,
+        };
+
+        invalidate_dimensions(&mut gui, &display_with(d, config.clone())).unwrap();
+
+        let nodes = match &gui {
+            Node::V(layout) => &layout.nodes,
+            _ => unreachable!(),
+        };
+
+        match (&nodes[0], &nodes[2], &nodes[4]) {
+            (Node::Tile(a), Node::Tile(b), Node::Tile(c)) => {
+                // Margin is taken from the area given by the layout
+                assert_eq!(
+                    (a.dim.x, a.dim.y, a.dim.width, a.dim.height),
+                    (5, 5, 290, 32)
+                );
+                // Tile's own padding, then display default
+                assert_eq!((b.content().x, b.content().width), (11, 278));
+                assert_eq!((c.content().x, c.content().y), (3, 83));
+            }
+            _ => unreachable!(),
+        }
+
+        assert_eq!((nodes[1].dim().x, nodes[1].dim().width), (6, 288));
+        assert_eq!((nodes[3].dim().x, nodes[3].dim().width), (1, 298));
+
+        let bc = render_to_bc(&gui, &display(d), "Sample");
+        // Refresh rect of C is its content
+        assert!(bc.source.contains("{3, 83, 297, 119}"));
+    }
+
+    #[test]
+    fn text_alignment_inside_content() {
+        let mut gui = h_layout([
+            tile("8848.94[m]")
+                .with_font_size(24)
+                .with_align(HAlign::Right, VAlign::Top),
+            tile("pace")
+                .with_font_size(24)
+                .with_align(HAlign::Left, VAlign::Bottom),
+            tile("10.20")
+                .with_font_size(24)
+                .with_align(HAlign::Center, VAlign::Baseline),
+        ]);
+
+        let d = Dimension {
+            x: 0,
+            y: 0,
+            width: 450,
+            height: 62,
+        };
+
+        invalidate_dimensions(&mut gui, &display(d)).unwrap();
+
+        let texts: Vec<_> = match &gui {
+            Node::H(layout) => layout
+                .nodes
+                .iter()
+                .map(|node| match node {
+                    Node::Tile(tile) => (tile.text.dim.x, tile.text.dim.y),
+                    _ => unreachable!(),
+                })
+                .collect(),
+            _ => unreachable!(),
+        };
+
+        // Tiles are 150x62, content 148x60, 24 font is 14px wide
+        assert_eq!(
+            texts,
+            vec![
+                (1 + 148 - 140, 1),
+                (1, 1 + 60 - 24),
+                (1 + (148 - 70) / 2, 1 + 60 - 20)
+            ]
+        );
+
+        let bc = render_to_bc(&gui, &display(d), "Sample");
+        assert!(bc
+            .source
+            .contains(r#"paint.DrawStringAt(9, 1, "8848.94[m]", &Font24, COLORED);"#));
+
+        let preview = render_to_60fps(&gui, &display(d));
+        assert!(preview.contains("horizontal-alignment: right;"));
+        assert!(preview.contains("horizontal-alignment: left;"));
+    }
+
+    #[test]
+    fn containers_pass_font_to_children() {
+        let mut gui = h_layout([
+            v_layout([tile("pace"), tile("10.20").with_font_size(19)]),
+            v_split(
+                tile("HR"),
+                0.5,
+                v_list(["Resume", "Save"]).with_font_size(19),
+            )
+            .with_font_size(31),
+            v_list(["Discard"]),
+        ])
+        .with_font_size(24);
+
+        let d = Dimension {
+            x: 0,
+            y: 0,
+            width: 400,
+            height: 120,
+        };
+
+        invalidate_dimensions(&mut gui, &display(d)).unwrap();
+
+        let nodes = match &gui {
+            Node::H(layout) => &layout.nodes,
+            _ => unreachable!(),
+        };
+        let fonts: Vec<_> = match (&nodes[0], &nodes[1], &nodes[2]) {
+            (Node::V(column), Node::VV(split), Node::VListWidget(list)) => {
+                let mut fonts: Vec<_> = column
+                    .nodes
+                    .iter()
+                    .map(|node| match node {
+                        Node::Tile(tile) => tile.text.font,
+                        _ => unreachable!(),
+                    })
+                    .collect();
+                match (split.first.as_ref(), split.second.as_ref()) {
+                    (Node::Tile(tile), Node::VListWidget(inner)) => {
+                        fonts.push(tile.text.font);
+                        fonts.push(inner.font);
+                        fonts.extend(inner.elements.iter().map(|e| e.text.font));
+                    }
+                    _ => unreachable!(),
+                }
+                fonts.push(list.font);
+                fonts.extend(list.elements.iter().map(|e| e.text.font));
+                fonts
+            }
+            _ => unreachable!(),
+        };
+
+        // Closest parent wins, own setting always wins
+        assert_eq!(
+            fonts,
+            [24, 19, 31, 19, 19, 19, 24, 24]
+                .iter()
+                .map(|&size| Some(size))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn font_group_shares_biggest_common_font() {
+        let mut gui = h_layout([
+            v_layout([
+                tile("pace").font_group("metrics"),
+                tile("10.20").font_group("metrics"),
+            ]),
+            v_layout([
+                tile("stride").font_group("metrics"),
+                tile("1.23").font_group("metrics").with_font_size(19),
+            ]),
+            tile("HR"),
+        ])
+        .with_font_size(24);
+
+        let d = Dimension {
+            x: 0,
+            y: 0,
+            width: 360,
+            height: 120,
+        };
+
+        invalidate_dimensions(&mut gui, &display(d)).unwrap();
+
+        let fonts: Vec<_> = match &gui {
+            Node::H(layout) => layout
+                .nodes
+                .iter()
+                .flat_map(|node| match node {
+                    Node::V(column) => column.nodes.iter().collect(),
+                    _ => vec![node],
+                })
+                .map(|node| match node {
+                    Node::Tile(tile) => tile.text.font,
+                    _ => unreachable!(),
+                })
+                .collect(),
+            _ => unreachable!(),
+        };
+
+        // Content is 118x58, "stride" fits at most 31, "pace" alone would get 42,
+        // explicit font leaves the group, inherited one does not apply to it
+        assert_eq!(
+            fonts,
+            vec![Some(31), Some(31), Some(31), Some(19), Some(24)]
+        );
+
+        let bc = render_to_bc(&gui, &display(d), "Sample");
+        assert!(bc.source.contains(r#"// Font group "metrics": Font31"#));
+    }
+
+    #[test]
+    fn proportional_fonts_measure_each_glyph() {
+        let fonts = FontSet::new([
+            Font::monospace(16, 12).with_advances([('i', 3), ('l', 3), ('W', 12)]),
+            Font::monospace(24, 20)
+                .with_advances([('i', 4), ('l', 4), ('W', 20)])
+                .with_kerning([(('i', 'l'), -1)]),
+        ]);
+        let config = LayoutConfig {
+            fonts,
+            ..Default::default()
+        };
+        let mut gui = h_layout([tile("ill"), tile("WW")]);
+
+        let d = Dimension {
+            x: 0,
+            y: 0,
+            width: 84,
+            height: 30,
+        };
+
+        invalidate_dimensions(&mut gui, &display_with(d, config.clone())).unwrap();
+
+        let texts: Vec<_> = match &gui {
+            Node::H(layout) => layout
+                .nodes
+                .iter()
+                .map(|node| match node {
+                    Node::Tile(tile) => (tile.text.font, tile.text.dim.x),
+                    _ => unreachable!(),
+                })
+                .collect(),
+            _ => unreachable!(),
+        };
+
+        // Content is 40px wide, "ill" takes 11px in 24, "WW" 40px in 24 and 24px in 16
+        assert_eq!(
+            texts,
+            vec![(Some(24), 1 + (40 - 11) / 2), (Some(16), 1 + (40 - 24) / 2)]
+        );
+    }
+
+    #[test]
+    fn missing_glyphs_are_reported_per_node() {
+        let page = || {
+            v_layout([
+                tile("23.19°C")
+                    .with_format("%5.2f°C")
+                    .bind("temperature", CType::F32),
+                tile("50%").with_format("%d%%").bind("battery", CType::U8),
+                v_list(["Yoga", "Café"]).with_font_size(19),
+            ])
+        };
+
+        let d = Dimension {
+            x: 0,
+            y: 0,
+            width: 400,
+            height: 240,
+        };
+
+        let mut gui = page();
+        let report = invalidate_dimensions(&mut gui, &display(d)).unwrap();
+
+        let missing: Vec<_> = report
+            .missing_glyphs
+            .iter()
+            .map(|missing| (missing.path.as_str(), missing.chars.clone()))
+            .collect();
+        assert_eq!(
+            missing,
+            vec![
+                (r#"root/V[0]/Tile("23.19°C")"#, vec!['°']),
+                (r#"root/V[2]/VListWidget[1]/Tile("Café")"#, vec!['é']),
+            ]
+        );
+        assert_eq!(
+            report.missing_glyphs[1].to_string(),
+            r#"root/V[2]/VListWidget[1]/Tile("Café"): font 19 has no glyphs for U+00E9 'é'"#
+        );
+
+        let strict = LayoutConfig {
+            strict_glyphs: true,
+            ..Default::default()
+        };
+        let mut gui = page();
+        assert!(matches!(
+            invalidate_dimensions(&mut gui, &display_with(d, strict.clone())),
+            Err(LayoutError::MissingGlyphs(missing)) if missing.chars == ['°']
+        ));
+    }
+
+    #[test]
+    fn wrapped_text_picks_biggest_font_fitting_the_paragraph() {
+        let config = LayoutConfig {
+            fonts: FontSet::waveshare(),
+            ..Default::default()
+        };
+        let mut gui = h_layout([tile("Hit button below to calculate your BMI")
+            .wrapped()
+            .with_align(HAlign::Left, VAlign::Top)]);
+
+        let d = Dimension {
+            x: 0,
+            y: 0,
+            width: 142,
+            height: 62,
+        };
+
+        invalidate_dimensions(&mut gui, &display_with(d, config.clone())).unwrap();
+
+        let tile = match &gui {
+            Node::H(layout) => match &layout.nodes[0] {
+                Node::Tile(tile) => tile,
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+
+        // Content is 140x60, Font16 needs 4 lines of 16px, Font12 only 2
+        assert_eq!(tile.text.font, Some(12));
+        let lines: Vec<_> = tile
+            .text
+            .lines
+            .iter()
+            .map(|line| (line.x, line.y, line.text.as_str()))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![(1, 1, "Hit button below to"), (1, 13, "calculate your BMI"),]
+        );
+
+        let bc = render_to_bc(&gui, &display(d), "Sample");
+        assert!(bc
+            .source
+            .contains(r#"paint.DrawStringAt(1, 13, "calculate your BMI", &Font12, COLORED);"#));
+
+        let preview = render_to_60fps(&gui, &display(d));
+        assert!(preview.contains("wrap: word-wrap;"));
+    }
+
+    #[test]
+    fn wrapped_text_cannot_be_formatted() {
+        let d = DisplayProfile::waveshare_2in9().dim;
+
+        let mut gui = h_layout([tile("Steps: 1234").with_format("Steps: %d").wrapped()]);
+        let err = invalidate_dimensions(&mut gui, &display(d)).unwrap_err();
+        assert_eq!(
+            err,
+            LayoutError::WrappedDynamic {
+                path: r#"root/H[0]/Tile("Steps: 1234")"#.into()
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            r#"root/H[0]/Tile("Steps: 1234"): wrapped text cannot change at runtime"#
+        );
+
+        let mut gui = h_layout([tile("Marathon")
+            .with_format("%s")
+            .bind("activity", CType::Str)
+            .wrapped()]);
+        assert!(matches!(
+            invalidate_dimensions(&mut gui, &display(d)),
+            Err(LayoutError::WrappedDynamic { .. })
+        ));
+    }
+
+    #[test]
+    fn overflow_policy_decides_about_text_too_long() {
+        let config = LayoutConfig {
+            fonts: FontSet::waveshare(),
+            ..Default::default()
+        };
+        let mut gui = h_layout([
+            tile("Half Marathon"),
+            tile("Half Marathon").with_overflow(TextOverflow::Ellipsize),
+            tile("Half Marathon")
+                .with_format("%s")
+                .bind("activity", CType::Str)
+                .with_overflow(TextOverflow::Clip),
+            tile("5k").with_overflow(TextOverflow::Fail),
+        ]);
+
+        let d = Dimension {
+            x: 0,
+            y: 0,
+            width: 248,
+            height: 20,
+        };
+
+        let report = invalidate_dimensions(&mut gui, &display_with(d, config.clone())).unwrap();
+
+        // Content is 60x18, even Font8 needs 65px, cutting keeps Font16 of 11px
+        let overflowed: Vec<_> = report
+            .overflowed
+            .iter()
+            .map(|o| (o.font_size, o.overflow, o.shown.as_str()))
+            .collect();
+        assert_eq!(
+            overflowed,
+            vec![
+                (8, TextOverflow::Shrink, "Half Marathon"),
+                (16, TextOverflow::Ellipsize, "Ha..."),
+                (16, TextOverflow::Clip, "Half "),
+            ]
+        );
+        assert_eq!(
+            report.overflowed[1].to_string(),
+            r#"root/H[1]/Tile("Half Marathon"): text ellipsized to "Ha..." in font 16"#
+        );
+
+        let bc = render_to_bc(&gui, &display(d), "Sample");
+        assert!(bc.source.contains(r#""Ha...", &Font16, COLORED);"#));
+        assert!(bc.source.contains(r#"message[5] = '\0';"#));
+
+        let preview = render_to_60fps(&gui, &display(d));
+        assert!(preview.contains(r#"text: "Ha...";"#));
+
+        let mut gui = h_layout([tile("Half Marathon").with_overflow(TextOverflow::Fail)]);
+        assert_eq!(
+            invalidate_dimensions(
+                &mut gui,
+                &display_with(Dimension { width: 62, ..d }, config.clone())
+            ),
+            Err(LayoutError::TextDoesNotFit {
+                path: r#"root/H[0]/Tile("Half Marathon")"#.into(),
+                font_size: 8
+            })
+        );
+    }
+
+    #[test]
+    fn list_scrolls_elements_past_visible_ones() {
+        let mut gui = v_layout([v_list([
+            "Running",
+            "Cycling",
+            "Hiking",
+            "Ind. Cycling",
+            "Yoga",
+            "Swimming",
+        ])
+        .with_visible(4)
+        .with_font_size(31)]);
+
+        let d = Dimension {
+            x: 0,
+            y: 0,
+            width: 240,
+            height: 218,
+        };
+
+        invalidate_dimensions(&mut gui, &display(d)).unwrap();
+
+        let slots: Vec<_> = match &gui {
+            Node::V(layout) => match &layout.nodes[0] {
+                Node::VListWidget(list) => list
+                    .elements
+                    .iter()
+                    .map(|tile| (tile.dim.y, tile.dim.height))
+                    .collect(),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+
+        // Content is 216px high, 4 slots of 54px, second page reuses them
+        assert_eq!(
+            slots,
+            vec![(1, 54), (55, 54), (109, 54), (163, 54), (1, 54), (55, 54)]
+        );
+
+        let bc = render_to_bc(&gui, &display(d), "Sample");
+        assert!(bc
+            .source
+            .contains("Font31, {1, 1, 239, 217}, 4, -1, Highlight::Inverted)"));
+
+        let preview = render_to_60fps(&gui, &display(d));
+        assert!(preview.contains(r#"text: "Ind. Cycling";"#));
+        assert!(!preview.contains(r#"text: "Yoga";"#));
+        // Scroll bar covers 4 out of 6 elements
+        assert!(preview.contains("x: 236phx;\n    y: 1phx;\n    width: 3phx;\n    height: 144phx;"));
+    }
+
+    #[test]
+    fn selected_element_is_highlighted_on_its_page() {
+        let list = || {
+            v_list(["Running", "Cycling", "Hiking", "Yoga", "Swimming"])
+                .with_visible(2)
+                .with_font_size(24)
+        };
+
+        let d = Dimension {
+            x: 0,
+            y: 0,
+            width: 200,
+            height: 102,
+        };
+
+        let mut gui = v_layout([list().with_selected(3, Highlight::Inverted)]);
+        invalidate_dimensions(&mut gui, &display(d)).unwrap();
+
+        let bc = render_to_bc(&gui, &display(d), "Sample");
+        assert!(bc
+            .source
+            .contains("Font24, {1, 1, 199, 101}, 2, 3, Highlight::Inverted)"));
+
+        // Page with "Yoga" is the second one, scroll bar starts at 2/5 of 100px
+        let preview = render_to_60fps(&gui, &display(d));
+        assert!(!preview.contains(r#"text: "Cycling";"#));
+        assert!(preview.contains(r#"text: "Hiking";"#));
+        assert!(preview.contains("y: 41phx;\n    width: 3phx;\n    height: 40phx;"));
+        let yoga = preview.find(r#"text: "Yoga";"#).unwrap();
+        let rectangle = &preview[preview[..yoga].rfind("Rectangle").unwrap()..];
+        let rectangle = &rectangle[..rectangle.find("}").unwrap()];
+        assert!(rectangle.contains("background: black;"));
+        assert!(rectangle.contains("color: white;"));
+
+        let mut gui = v_layout([list().with_selected(5, Highlight::Frame)]);
+        assert_eq!(
+            invalidate_dimensions(&mut gui, &display(d)),
+            Err(LayoutError::NoSuchElement {
+                path: "root/V[0]/VListWidget".into(),
+                selected: 5,
+                elements: 5
+            })
+        );
+    }
+
+    #[test]
+    fn format_kinds_generate_matching_code() {
+        let mut gui = h_layout([
+            tile("21:37")
+                .with_strftime("%H:%M")
+                .bind("clock", CType::Time),
+            tile("01:12:20")
+                .with_duration("%T")
+                .bind("lap.elapsed", CType::U32),
+            tile("23.19")
+                .with_format("%5.2f")
+                .bind("temperature", CType::F32),
+        ]);
+
+        let d = Dimension {
+            x: 0,
+            y: 0,
+            width: 400,
+            height: 40,
+        };
+
+        invalidate_dimensions(&mut gui, &display(d)).unwrap();
+
+        let bc = render_to_bc(&gui, &display(d), "Sample");
+        assert!(bc
+            .source
+            .contains(r#"strftime(message, msg_size, "%H:%M", &data.clock);"#));
+        assert!(bc.source.contains(
+            r#"snprintf(message, msg_size, "%02u:%02u:%02u", seconds / 3600, seconds / 60 % 60, seconds % 60);"#
+        ));
+        assert!(bc
+            .source
+            .contains(r#"snprintf(message, msg_size, "%5.2f", data.temperature);"#));
+
+        // Nothing to print
+        let mut gui = h_layout([tile("21:37").with_strftime("%H:%M")]);
+        assert_eq!(
+            invalidate_dimensions(&mut gui, &display(d))
+                .unwrap_err()
+                .to_string(),
+            r#"root/H[0]/Tile("21:37"): format takes 1 values, but 0 fields are bound"#
+        );
+
+        // Time pattern given to snprintf
+        let mut gui = h_layout([tile("21:37").with_format("%T")]);
+        assert_eq!(
+            invalidate_dimensions(&mut gui, &display(d))
+                .unwrap_err()
+                .to_string(),
+            r#"root/H[0]/Tile("21:37"): "%T" is not a printf conversion"#
+        );
+    }
+
+    #[test]
+    fn bindings_fill_format_arguments() {
+        let mut gui = h_layout([
+            tile("13 / 11")
+                .with_format("%d / %d")
+                .bind("gps.in_view", CType::U8)
+                .bind("gps.tracked", CType::U8),
+            tile("21:37")
+                .with_strftime("%H:%M")
+                .bind("clock", CType::Time),
+            tile("01:12:20")
+                .with_duration("%T")
+                .bind("lap.elapsed", CType::U32),
+        ]);
+
+        let d = Dimension {
+            x: 0,
+            y: 0,
+            width: 400,
+            height: 40,
+        };
+
+        invalidate_dimensions(&mut gui, &display(d)).unwrap();
+
+        let bc = render_to_bc(&gui, &display(d), "Sample");
+        assert!(bc.source.contains(
+            r#"snprintf(message, msg_size, "%d / %d", data.gps.in_view, data.gps.tracked);"#
+        ));
+        assert!(bc
+            .source
+            .contains(r#"strftime(message, msg_size, "%H:%M", &data.clock);"#));
+        assert!(bc
+            .source
+            .contains("const uint32_t seconds = data.lap.elapsed;"));
+
+        // One value short
+        let mut gui = h_layout([tile("13 / 11")
+            .with_format("%d / %d")
+            .bind("gps.in_view", CType::U8)]);
+        assert_eq!(
+            invalidate_dimensions(&mut gui, &display(d))
+                .unwrap_err()
+                .to_string(),
+            r#"root/H[0]/Tile("13 / 11"): format takes 2 values, but 1 fields are bound"#
+        );
+
+        // Float printed as an integer
+        let mut gui = h_layout([tile("23.19")
+            .with_format("%d")
+            .bind("temperature", CType::F32)]);
+        assert_eq!(
+            invalidate_dimensions(&mut gui, &display(d))
+                .unwrap_err()
+                .to_string(),
+            r#"root/H[0]/Tile("23.19"): "%d" cannot print temperature of type float"#
+        );
+
+        // Seconds are not a calendar time
+        let mut gui = h_layout([tile("21:37")
+            .with_strftime("%H:%M")
+            .bind("lap.elapsed", CType::U32)]);
+        assert_eq!(
+            invalidate_dimensions(&mut gui, &display(d))
+                .unwrap_err()
+                .to_string(),
+            r#"root/H[0]/Tile("21:37"): "%H:%M" cannot print lap.elapsed of type uint32_t"#
+        );
+    }
+
+    #[test]
+    fn font_fits_longest_value_of_the_range() {
+        let font_of = |gui: &Node| match gui {
+            Node::H(layout) => match &layout.nodes[0] {
+                Node::Tile(tile) => tile.text.font.unwrap(),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+
+        let d = Dimension {
+            x: 0,
+            y: 0,
+            width: 100,
+            height: 60,
+        };
+
+        // Sample is all there is to measure
+        let mut sample = h_layout([tile("23.19")
+            .with_format("%5.2f")
+            .bind("temperature", CType::F32)]);
+        invalidate_dimensions(&mut sample, &display(d)).unwrap();
+
+        let mut ranged = h_layout([tile("23.19")
+            .with_format("%5.2f")
+            .bind("temperature", CType::F32)
+            .with_range(-123.45, 99.0)]);
+        invalidate_dimensions(&mut ranged, &display(d)).unwrap();
+
+        assert!(font_of(&ranged) < font_of(&sample));
+
+        // Buffer holds "-123.45" and the '\0'
+        assert!(render_to_bc(&ranged, &display(d), "Sample")
+            .source
+            .contains("const int msg_size = 8;"));
+        assert!(render_to_bc(&sample, &display(d), "Sample")
+            .source
+            .contains("const int msg_size = 128;"));
+
+        // Name of the time zone may be of any length
+        let mut zoned = h_layout([tile("21:37 CEST")
+            .with_strftime("%H:%M %Z")
+            .bind("clock", CType::Time)]);
+        invalidate_dimensions(&mut zoned, &display(d)).unwrap();
+        assert!(render_to_bc(&zoned, &display(d), "Sample")
+            .source
+            .contains("const int msg_size = 128;"));
+    }
+
+    #[test]
+    fn page_becomes_view_class() {
+        let mut gui = h_layout([
+            v_layout([
+                tile("pace"),
+                tile("10.20")
+                    .with_format("%.2f")
+                    .bind("pace", CType::F32)
+                    .with_range(0.0, 99.0),
+            ]),
+            v_line(),
+            v_list(["Resume", "Save"]).with_font_size(24),
+            tile("static").dynamic("static"),
+        ]);
+
+        let d = Dimension {
+            x: 0,
+            y: 0,
+            width: 400,
+            height: 240,
+        };
+
+        invalidate_dimensions(&mut gui, &display(d)).unwrap();
+
+        let view = render_to_bc(&gui, &display(d), "Running");
+        assert_eq!(view.class, "RunningView");
+
+        assert!(view.header.contains("class RunningView : public View {"));
+        assert!(view.header.contains("#include \"VListWidget.h\""));
+        // Label is drawn with the static elements, value gets the method
+        assert!(view.header.contains("void drawPace(const ViewData &data);"));
+        assert!(!view.header.contains("drawPace2"));
+        assert!(view.header.contains("    VListWidget list0_;"));
+        // Method of the view is not overloaded
+        assert!(view
+            .header
+            .contains("void drawStatic2(const ViewData &data);"));
+
+        assert!(view.source.contains("#include \"RunningView.h\""));
+        for header in ["<cstdio>", "<cstring>", "<ctime>"] {
+            assert!(view.source.contains(&format!("#include {}", header)));
+        }
+        assert!(view
+            .source
+            .contains(r#"list0_(display, {"Resume", "Save"}, Font"#));
+        assert!(view.source.contains("void RunningView::drawStatic() {"));
+        let draw_static = view.source.split("void RunningView::draw(").next().unwrap();
+        assert!(draw_static.contains(r#", "pace", &Font"#));
+        assert!(view.source.contains("    drawPace(data);"));
+        assert!(view
+            .source
+            .contains("void RunningView::drawPace(const ViewData &data) {"));
+    }
+
+    #[test]
+    fn static_refresh_covers_the_lines() {
+        let mut gui = v_layout([
+            tile("status").dynamic("status"),
+            h_line(),
+            h_layout([tile("A").dynamic("a"), v_line(), tile("B").dynamic("b")]),
+        ]);
+
+        let d = Dimension {
+            x: 0,
+            y: 0,
+            width: 400,
+            height: 240,
+        };
+
+        invalidate_dimensions(&mut gui, &display(d)).unwrap();
+
+        let area = match &gui {
+            Node::V(page) => match (&page.nodes[1], &page.nodes[2]) {
+                (Node::HorizontalLine(h), Node::H(row)) => match &row.nodes[1] {
+                    Node::VerticalLine(v) => h.dim.union(&v.dim),
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+
+        let view = render_to_bc(&gui, &display(d), "Sample");
+        assert!(view.source.contains(&format!(
+            "{{{}, {}, {}, {}}});",
+            area.x,
+            area.y,
+            area.x + area.width,
+            area.y + area.height
+        )));
+        assert!(!view.source.contains("display_->getWidth(), 13}"));
+
+        // Without padding, content of the tile reaches the line
+        let config = LayoutConfig {
+            padding: Edges::default(),
+            h_line_margin: Edges::default(),
+            ..Default::default()
+        };
+        let mut gui = v_layout([tile("status"), h_line(), tile("A").dynamic("a")]);
+        assert_eq!(
+            invalidate_dimensions(&mut gui, &display_with(d, config.clone()))
+                .unwrap_err()
+                .to_string(),
+            r#"root/V[2]/Tile("A"): refreshed area overlaps root/V[1]/HorizontalLine"#
+        );
+    }
+
+    #[test]
+    fn labels_are_static_unless_dynamic() {
+        let mut gui = h_layout([
+            tile("pace"),
+            tile("lap").dynamic("lap"),
+            tile("1.23").with_format("%.2f").bind("stride", CType::F32),
+        ]);
+
+        let d = Dimension {
+            x: 0,
+            y: 0,
+            width: 400,
+            height: 40,
+        };
+
+        invalidate_dimensions(&mut gui, &display(d)).unwrap();
+
+        let view = render_to_bc(&gui, &display(d), "Sample");
+        assert!(!view.header.contains("drawPace"));
+        assert!(view.header.contains("void drawLap(const ViewData &data);"));
+        assert!(view
+            .header
+            .contains("void drawStride(const ViewData &data);"));
+        assert!(view.source.contains("enqueueStaticDraw"));
+        assert!(view
+            .source
+            .contains(r#"snprintf(message, msg_size, "%s", data.lap);"#));
+
+        // Preview draws the static layer after the dynamic one
+        let preview = render_to_60fps(&gui, &display(d));
+        let position = |text| preview.find(text).unwrap();
+        assert!(position(r#"text: "pace";"#) > position(r#"text: "lap";"#));
+        assert!(position(r#"text: "pace";"#) > position(r#"text: "1.23";"#));
+
+        // Label without format shows a string
+        let mut gui = h_layout([tile("3").bind("gps.fix", CType::U8)]);
+        assert_eq!(
+            invalidate_dimensions(&mut gui, &display(d))
+                .unwrap_err()
+                .to_string(),
+            r#"root/H[0]/Tile("3"): "%s" cannot print gps.fix of type uint8_t"#
+        );
+    }
+
+    #[test]
+    fn texts_are_escaped_in_c_strings() {
+        let mut gui = h_layout([
+            tile(r#"say "hi" \o/"#),
+            tile("100%"),
+            tile("21°C")
+                .with_format("%d°C")
+                .bind("temperature", CType::I32),
+            tile("Marathon").dynamic("activity"),
+        ]);
+
+        let d = Dimension {
+            x: 0,
+            y: 0,
+            width: 400,
+            height: 40,
+        };
+
+        invalidate_dimensions(&mut gui, &display(d)).unwrap();
+
+        let source = render_to_bc(&gui, &display(d), "Sample").source;
+        assert!(source.contains(r#", "say \"hi\" \\o/", &Font"#));
+        assert!(source.contains(r#", "100%", &Font"#));
+        assert!(source.contains(r#"snprintf(message, msg_size, "%s", data.activity);"#));
+        assert!(source.contains(r#"snprintf(message, msg_size, "%d\302\260C", data.temperature);"#));
+    }
+
+    #[test]
+    fn tiles_of_the_same_source_share_refresh() {
+        let mut gui = v_layout([
+            tile("in view: 13")
+                .with_format("in view: %d")
+                .bind("gps.in_view", CType::U8),
+            tile("tracked: 11")
+                .with_format("tracked: %d")
+                .bind("gps.tracked", CType::U8),
+            h_line(),
+            tile("GPS 3D")
+                .with_format("GPS %1d")
+                .bind("gps.fix", CType::U8)
+                .with_range(0.0, 3.0),
+            tile("23.19")
+                .with_format("%5.2f")
+                .bind("temperature", CType::F32)
+                .with_range(-40.0, 85.0)
+                .with_source("gps"),
+        ]);
+
+        let d = Dimension {
+            x: 0,
+            y: 0,
+            width: 400,
+            height: 240,
+        };
+
+        invalidate_dimensions(&mut gui, &display(d)).unwrap();
+
+        let view = render_to_bc(&gui, &display(d), "Sample");
+        let regions: Vec<_> = view
+            .refresh
+            .regions
+            .iter()
+            .map(|region| (region.method.as_str(), region.tiles))
+            .collect();
+        // Line splits the tiles of the gps, merged area would wipe it out
+        assert_eq!(regions, vec![("Gps", 2), ("Gps2", 2)]);
+
+        let pixels: usize = view
+            .refresh
+            .regions
+            .iter()
+            .map(|region| region.area.width * region.area.height)
+            .sum();
+        assert_eq!(view.refresh.pixels(), pixels);
+        assert!(view
+            .refresh
+            .to_string()
+            .starts_with(&format!("2 refreshes, {} pixels per update", pixels)));
+
+        // Each tile of the merged refresh has its own message buffer
+        assert!(view.source.contains("// in view: 13, tracked: 11"));
+        assert_eq!(view.source.matches("char message[msg_size];").count(), 4);
+    }
+
+    #[test]
+    fn one_tree_is_laid_out_for_every_display() {
+        let mut gui = v_layout([
+            h_layout([
+                tile("pace"),
+                tile("10.20")
+                    .with_format("%.2f")
+                    .bind("pace", CType::F32)
+                    .font_group("values"),
+            ]),
+            h_line(),
+            h_layout([
+                tile("cadence").with_padding(Edges::symmetric(2, 8)),
+                tile("158")
+                    .with_format("%3d")
+                    .bind("cadence", CType::U16)
+                    .font_group("values"),
+            ]),
+            v_list(["Resume", "Save"])
+                .with_visible(1)
+                .with_font_size(24),
+        ]);
+        let sharp = DisplayProfile::sharp_mip_2in7();
+        let waveshare = DisplayProfile::waveshare_2in9();
+
+        invalidate_dimensions(&mut gui, &sharp).unwrap();
+        let first = render_to_bc(&gui, &sharp, "Sample").source;
 
-        render_to_60fps(&gui, &d);
+        invalidate_dimensions(&mut gui, &waveshare).unwrap();
+        assert_leaves_cover(&gui, &waveshare.dim);
+        for (_, font) in common::font_groups(&gui) {
+            assert!(waveshare.layout.fonts.get(font).is_some());
+        }
+
+        // Nothing is left behind by the layout for the other display
+        invalidate_dimensions(&mut gui, &sharp).unwrap();
+        assert_eq!(render_to_bc(&gui, &sharp, "Sample").source, first);
     }
 
+    #[test]
+    fn refresh_is_aligned_for_the_controller() {
+        let waveshare = DisplayProfile::waveshare_2in9();
 
+        let mut gui = h_layout([
+            tile("21:37")
+                .with_strftime("%H:%M")
+                .bind("clock", CType::Time),
+            v_line(),
+            tile("GPS 3D")
+                .with_format("GPS %1d")
+                .bind("gps.fix", CType::U8),
+        ]);
+        invalidate_dimensions(&mut gui, &waveshare).unwrap();
+
+        let refreshes: Vec<_> = match &gui {
+            Node::H(layout) => layout
+                .nodes
+                .iter()
+                .filter_map(|node| match node {
+                    Node::Tile(tile) => Some(tile.refresh),
+                    _ => None,
+                })
+                .collect(),
+            _ => unreachable!(),
+        };
+        for refresh in &refreshes {
+            assert_eq!((refresh.x % 8, refresh.width % 8), (0, 0));
+        }
+
+        let bc = render_to_bc(&gui, &waveshare, "Sample");
+        let area = refreshes[1];
+        assert!(bc.source.contains(&format!(
+            "{{{}, {}, {}, {}}});",
+            area.x,
+            area.y,
+            area.x + area.width,
+            area.y + area.height
+        )));
+
+        // Padding thinner than the alignment lets the refresh grow past the tile
+        let mut thin = waveshare.clone();
+        thin.layout.padding = Edges::all(1);
+        let mut gui = h_layout([
+            tile("21:37")
+                .with_strftime("%H:%M")
+                .bind("clock", CType::Time),
+            tile("GPS 3D")
+                .with_format("GPS %1d")
+                .bind("gps.fix", CType::U8),
+        ]);
+        assert_eq!(
+            invalidate_dimensions(&mut gui, &thin)
+                .unwrap_err()
+                .to_string(),
+            r#"root/H[0]/Tile("21:37"): refreshed area overlaps root/H[1]/Tile("GPS 3D")"#
+        );
+    }
 }
@@ -1,20 +1,30 @@
 #[cfg(test)]
 mod test {
-    use crate::{bc_render::render_to_bc, common::{
-            h_layout, h_line, h_split, invalidate_dimensions, tile, v_layout, v_line, v_list,
-            Dimension,
-        }, mocks_sharp_mip_2in7::common_params::display_dimension, sixtyfps_render::render_to_60fps};
-
-    use super::*;
+    use crate::{
+        bc_render::render_to_bc,
+        common::{
+            assert_leaves_cover, h_layout, h_line, invalidate_dimensions, tile, v_layout, v_line,
+            Size,
+        },
+        display::DisplayProfile,
+        format::CType,
+        sixtyfps_render::render_to_60fps,
+    };
 
     #[test]
     fn bt_splash() {
+        let display = DisplayProfile::sharp_mip_2in7();
+
         let status_bar = h_layout([
-            tile("21:37").with_format("%T"),
+            tile("21:37").with_strftime("%T").bind("clock", CType::Time),
             v_line(),
-            tile("GPS 3D").with_format("GPS %1d"),
+            tile("GPS 3D")
+                .with_format("GPS %1d")
+                .bind("gps.fix", CType::U8),
             v_line(),
-            tile("02/09/21").with_format("%d/%m/%y"),
+            tile("02/09/21")
+                .with_strftime("%d/%m/%y")
+                .bind("clock", CType::Time),
         ]);
         let welcome_page = v_layout([
             h_line(),
@@ -22,16 +32,20 @@ mod test {
             tile("ᛡ").with_font_size(56),
             tile("status").with_font_size(42),
             tile("Off").with_font_size(42),
+        ]);
 
+        let mut gui = v_layout([status_bar.with_height(Size::Pixels(24)), welcome_page]);
 
-        ]);
+        let report = invalidate_dimensions(&mut gui, &display).unwrap();
 
-        let mut gui = h_split(status_bar, 0.101, welcome_page);
+        // Rune is not in the device fonts, placeholder until there is an icon font
+        assert_eq!(report.missing_glyphs.len(), 1);
+        assert_eq!(report.missing_glyphs[0].chars, vec!['ᛡ']);
 
-        invalidate_dimensions(&mut gui, &display_dimension);
+        assert_leaves_cover(&gui, &display.dim);
 
-        render_to_60fps(&gui, &display_dimension);
+        render_to_60fps(&gui, &display);
 
-        render_to_bc(&gui, &display_dimension);
+        render_to_bc(&gui, &display, "BtSplash");
     }
 }
@@ -1,9 +0,0 @@
-use crate::common::Dimension;
-
-
-pub const display_dimension : Dimension = Dimension{
-    x: 0,
-    y: 0,
-    width: 400,
-    height: 240,
-};
\ No newline at end of file
@@ -1,58 +1,92 @@
 #[cfg(test)]
 mod test {
-    use crate::{bc_render::render_to_bc, common::{
-            h_layout, h_line, h_split, invalidate_dimensions, tile, v_layout, v_line, v_list,
-            Dimension,
-        }, mocks_sharp_mip_2in7::common_params::display_dimension, sixtyfps_render::render_to_60fps};
-
-    use super::*;
+    use crate::{
+        bc_render::render_to_bc,
+        common::{
+            assert_leaves_cover, h_layout, h_line, invalidate_dimensions, tile, v_layout, v_line,
+            HAlign, Size, VAlign,
+        },
+        display::DisplayProfile,
+        format::CType,
+        sixtyfps_render::render_to_60fps,
+    };
 
     #[test]
     fn bc_test_page() {
+        let display = DisplayProfile::sharp_mip_2in7();
+
         let status_bar = h_layout([
-            tile("21:37").with_format("%T"),
+            tile("21:37")
+                .with_strftime("%T")
+                .bind("clock", CType::Time)
+                .font_group("status"),
             v_line(),
-            tile("GPS 3D").with_format("GPS %1d"),
+            tile("GPS 3D")
+                .with_format("GPS %1d")
+                .bind("gps.fix", CType::U8)
+                .font_group("status"),
             v_line(),
-            tile("02/09/21").with_format("%d/%m/%y"),
+            tile("02/09/21")
+                .with_strftime("%d/%m/%y")
+                .bind("clock", CType::Time)
+                .font_group("status"),
         ]);
         let welcome_page = v_layout([
             h_line(),
             v_line(),
             h_layout([
                 v_layout([
-                    tile("02/09/21").with_format("%d/%m/%y").with_font_size(24),
-                    tile("19:34:19").with_format("%T").with_font_size(24),
+                    tile("02/09/21")
+                        .with_strftime("%d/%m/%y")
+                        .bind("clock", CType::Time)
+                        .with_font_size(24),
+                    tile("19:34:19")
+                        .with_strftime("%T")
+                        .bind("clock", CType::Time)
+                        .with_font_size(24),
                 ]),
                 v_layout([
                     tile("in view: 13")
                         .with_format("in view: %d")
+                        .bind("gps.in_view", CType::U8)
                         .with_font_size(24),
                     tile("tracked: 11")
                         .with_format("tracked: %d")
+                        .bind("gps.tracked", CType::U8)
                         .with_font_size(24),
                 ]),
             ]),
-            // Current implementation of invalidate_dimensions
-            // makes {h,v}_line split tiles defined after them
-            // so they 'stick' to the bottom
+            // h_line goes on the boundary with the widgets above,
+            // v_line splits the h_layout which follows it
             h_line(),
             v_line(),
             h_layout([
                 v_layout([
-                    tile("23.19[*C]").with_format("%5.2f[*C]").with_font_size(24),
-                    tile("8848.94[m]").with_format("%07.2f[m]").with_font_size(24),
+                    tile("23.19[*C]")
+                        .with_format("%5.2f[*C]")
+                        .bind("temperature", CType::F32)
+                        .with_range(-40.0, 85.0)
+                        .with_font_size(24)
+                        .with_align(HAlign::Right, VAlign::Center),
+                    tile("8848.94[m]")
+                        .with_format("%07.2f[m]")
+                        .bind("altitude", CType::F32)
+                        .with_range(-500.0, 9000.0)
+                        .with_font_size(24)
+                        .with_align(HAlign::Right, VAlign::Center),
                 ]),
                 v_layout([tile("")]),
             ]),
         ]);
 
-        let mut gui = h_split(status_bar, 0.101, welcome_page);
+        let mut gui = v_layout([status_bar.with_height(Size::Pixels(24)), welcome_page]);
+
+        invalidate_dimensions(&mut gui, &display).unwrap();
 
-        invalidate_dimensions(&mut gui, &display_dimension);
+        assert_leaves_cover(&gui, &display.dim);
 
-        render_to_60fps(&gui, &display_dimension);
+        render_to_60fps(&gui, &display);
 
-        render_to_bc(&gui, &display_dimension);
+        render_to_bc(&gui, &display, "Health");
     }
 }
@@ -1,8 +1,7 @@
-mod common_params;
-mod select_activity;
-mod welcome;
-mod stats;
-mod running;
 mod activity_paused;
+mod bluetooth;
 mod health;
-mod bluetooth;
\ No newline at end of file
+mod running;
+mod select_activity;
+mod stats;
+mod welcome;
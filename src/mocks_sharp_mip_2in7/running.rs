@@ -1,37 +1,41 @@
-use crate::mocks_sharp_mip_2in7::common_params::display_dimension;
-
 #[cfg(test)]
 mod test {
     use crate::{
         bc_render::render_to_bc,
         common::{
-            h_layout, h_line, h_split, invalidate_dimensions, tile, v_layout, v_line, v_list,
-            Dimension,
+            assert_leaves_cover, h_layout, h_line, invalidate_dimensions, tile, v_layout, v_line,
+            v_list, Size,
         },
+        display::DisplayProfile,
+        format::CType,
         sixtyfps_render::render_to_60fps,
     };
 
-    use super::*;
-
     #[test]
     fn page_1() {
+        let display = DisplayProfile::sharp_mip_2in7();
+
         let status_bar = h_layout([
-            tile("21:37").with_format("%T"),
+            tile("21:37").with_strftime("%T").bind("clock", CType::Time),
             v_line(),
-            tile("GPS 3D").with_format("GPS %1d"),
+            tile("GPS 3D")
+                .with_format("GPS %1d")
+                .bind("gps.fix", CType::U8),
             v_line(),
-            tile("02/09/21").with_format("%d/%m/%y"),
+            tile("02/09/21")
+                .with_strftime("%d/%m/%y")
+                .bind("clock", CType::Time),
         ]);
         let welcome_page = h_layout([
             v_layout([
                 v_layout([
-                    tile("pace").with_font_size(31),
-                    tile("10.20").with_format("%.2f").with_font_size(31),
+                    tile("pace"),
+                    tile("10.20").with_format("%.2f").bind("pace", CType::F32),
                 ]),
                 h_line(),
                 v_layout([
-                    tile("stride").with_font_size(31),
-                    tile("1.23").with_format("%.2f").with_font_size(31),
+                    tile("stride"),
+                    tile("1.23").with_format("%.2f").bind("stride", CType::F32),
                     // h_layout([
                     //     tile("lap time").with_font_size(19),
                     //     tile("02:03:04").with_format("%.2f").with_font_size(19),
@@ -45,105 +49,132 @@ mod test {
             v_line(),
             v_layout([
                 v_layout([
-                    tile("HR zone").with_font_size(31),
-                    tile("2.79").with_format("%.2f").with_font_size(31),
+                    tile("HR zone"),
+                    tile("2.79").with_format("%.2f").bind("hr_zone", CType::F32),
                 ]),
                 h_line(),
                 v_layout([
-                    tile("cadence").with_font_size(31),
-                    tile("158").with_format("%3d").with_font_size(31),
+                    tile("cadence"),
+                    tile("158").with_format("%3d").bind("cadence", CType::U16),
                 ]),
             ]),
-        ]);
+        ])
+        .with_font_size(31);
+
+        let mut gui = v_layout([status_bar.with_height(Size::Pixels(24)), welcome_page]);
 
-        let mut gui = h_split(status_bar, 0.101, welcome_page);
+        invalidate_dimensions(&mut gui, &display).unwrap();
 
-        invalidate_dimensions(&mut gui, &display_dimension);
+        assert_leaves_cover(&gui, &display.dim);
 
-        render_to_60fps(&gui, &display_dimension);
+        render_to_60fps(&gui, &display);
 
-        render_to_bc(&gui, &display_dimension);
+        render_to_bc(&gui, &display, "RunningPage1");
     }
 
     #[test]
     fn page_2() {
+        let display = DisplayProfile::sharp_mip_2in7();
+
         let status_bar = h_layout([
-            tile("21:37").with_format("%T"),
+            tile("21:37").with_strftime("%T").bind("clock", CType::Time),
             v_line(),
-            tile("GPS 3D").with_format("GPS %1d"),
+            tile("GPS 3D")
+                .with_format("GPS %1d")
+                .bind("gps.fix", CType::U8),
             v_line(),
-            tile("02/09/21").with_format("%d/%m/%y"),
+            tile("02/09/21")
+                .with_strftime("%d/%m/%y")
+                .bind("clock", CType::Time),
         ]);
         let welcome_page = h_layout([
             v_layout([
                 v_layout([
-                    tile("total dist").with_font_size(31),
-                    tile("10.20").with_format("%.2f").with_font_size(31),
+                    tile("total dist"),
+                    tile("10.20")
+                        .with_format("%.2f")
+                        .bind("total_dist", CType::F32),
                 ]),
                 h_line(),
                 v_layout([
-                    tile("lap dist").with_font_size(31),
-                    tile("5.20").with_format("%.2f").with_font_size(31),
+                    tile("lap dist"),
+                    tile("5.20")
+                        .with_format("%.2f")
+                        .bind("lap_dist", CType::F32),
                 ]),
             ]),
             v_line(),
             v_layout([
                 v_layout([
-                    tile("total time").with_font_size(31),
-                    tile("02:12:20").with_format("%T").with_font_size(31),
+                    tile("total time"),
+                    tile("02:12:20")
+                        .with_duration("%T")
+                        .bind("total_time", CType::U32),
                 ]),
                 h_line(),
                 v_layout([
-                    tile("lap time").with_font_size(31),
-                    tile("01:12:20").with_format("%T").with_font_size(31),
+                    tile("lap time"),
+                    tile("01:12:20")
+                        .with_duration("%T")
+                        .bind("lap_time", CType::U32),
                 ]),
             ]),
-        ]);
+        ])
+        .with_font_size(31);
+
+        let mut gui = v_layout([status_bar.with_height(Size::Pixels(24)), welcome_page]);
 
-        let mut gui = h_split(status_bar, 0.101, welcome_page);
+        invalidate_dimensions(&mut gui, &display).unwrap();
 
-        invalidate_dimensions(&mut gui, &display_dimension);
+        assert_leaves_cover(&gui, &display.dim);
 
-        render_to_60fps(&gui, &display_dimension);
+        render_to_60fps(&gui, &display);
 
-        render_to_bc(&gui, &display_dimension);
+        render_to_bc(&gui, &display, "RunningPage2");
     }
 
     #[test]
     fn workout_steps_splash() {
+        let display = DisplayProfile::sharp_mip_2in7();
+
         let status_bar = h_layout([
-            tile("21:37").with_format("%T"),
+            tile("21:37").with_strftime("%T").bind("clock", CType::Time),
             v_line(),
-            tile("GPS 3D").with_format("GPS %1d"),
+            tile("GPS 3D")
+                .with_format("GPS %1d")
+                .bind("gps.fix", CType::U8),
             v_line(),
-            tile("02/09/21").with_format("%d/%m/%y"),
+            tile("02/09/21")
+                .with_strftime("%d/%m/%y")
+                .bind("clock", CType::Time),
         ]);
         let welcome_page = v_layout([h_line(), h_layout([tile("Workout Steps")])]);
 
-        let mut gui = h_split(status_bar, 0.101, welcome_page);
+        let mut gui = v_layout([status_bar.with_height(Size::Pixels(24)), welcome_page]);
 
-        let d = Dimension {
-            x: 0,
-            y: 0,
-            width: 296,
-            height: 128,
-        };
+        invalidate_dimensions(&mut gui, &display).unwrap();
 
-        invalidate_dimensions(&mut gui, &display_dimension);
+        assert_leaves_cover(&gui, &display.dim);
 
-        render_to_60fps(&gui, &display_dimension);
+        render_to_60fps(&gui, &display);
 
-        render_to_bc(&gui, &display_dimension);
+        render_to_bc(&gui, &display, "WorkoutStepsSplash");
     }
 
     #[test]
     fn page_3() {
+        let display = DisplayProfile::sharp_mip_2in7();
+
         let status_bar = h_layout([
-            tile("21:37").with_format("%T"),
+            tile("21:37").with_strftime("%T").bind("clock", CType::Time),
             v_line(),
-            tile("GPS 3D").with_format("GPS %1d"),
+            tile("GPS 3D")
+                .with_format("GPS %1d")
+                .bind("gps.fix", CType::U8),
             v_line(),
-            tile("02/09/21").with_format("%d/%m/%y"),
+            tile("02/09/21")
+                .with_strftime("%d/%m/%y")
+                .bind("clock", CType::Time),
         ]);
         let welcome_page = h_layout([v_list([
             "run 5.00 1/5",
@@ -153,30 +184,31 @@ mod test {
         ])
         .with_font_size(19)]);
 
-        let mut gui = h_split(status_bar, 0.101, welcome_page);
+        let mut gui = v_layout([status_bar.with_height(Size::Pixels(24)), welcome_page]);
 
-        let d = Dimension {
-            x: 0,
-            y: 0,
-            width: 296,
-            height: 128,
-        };
+        invalidate_dimensions(&mut gui, &display).unwrap();
 
-        invalidate_dimensions(&mut gui, &display_dimension);
+        assert_leaves_cover(&gui, &display.dim);
 
-        render_to_60fps(&gui, &display_dimension);
+        render_to_60fps(&gui, &display);
 
-        render_to_bc(&gui, &display_dimension);
+        render_to_bc(&gui, &display, "RunningPage3");
     }
 
     #[test]
     fn page_paused() {
+        let display = DisplayProfile::sharp_mip_2in7();
+
         let status_bar = h_layout([
-            tile("21:37").with_format("%T"),
+            tile("21:37").with_strftime("%T").bind("clock", CType::Time),
             v_line(),
-            tile("GPS 3D").with_format("GPS %1d"),
+            tile("GPS 3D")
+                .with_format("GPS %1d")
+                .bind("gps.fix", CType::U8),
             v_line(),
-            tile("02/09/21").with_format("%d/%m/%y"),
+            tile("02/09/21")
+                .with_strftime("%d/%m/%y")
+                .bind("clock", CType::Time),
         ]);
         let welcome_page = v_layout([
             h_line(),
@@ -187,19 +219,14 @@ mod test {
             ]),
         ]);
 
-        let mut gui = h_split(status_bar, 0.101, welcome_page);
+        let mut gui = v_layout([status_bar.with_height(Size::Pixels(24)), welcome_page]);
 
-        let d = Dimension {
-            x: 0,
-            y: 0,
-            width: 296,
-            height: 128,
-        };
+        invalidate_dimensions(&mut gui, &display).unwrap();
 
-        invalidate_dimensions(&mut gui, &display_dimension);
+        assert_leaves_cover(&gui, &display.dim);
 
-        render_to_60fps(&gui, &display_dimension);
+        render_to_60fps(&gui, &display);
 
-        render_to_bc(&gui, &display_dimension);
+        render_to_bc(&gui, &display, "RunningPaused");
     }
 }
@@ -1,47 +1,65 @@
 #[cfg(test)]
 mod test {
-    use crate::{bc_render::render_to_bc, common::{
-            h_layout, h_line, h_split, invalidate_dimensions, tile, v_layout, v_line, v_list,
-            Dimension,
-        }, mocks_sharp_mip_2in7::common_params::display_dimension, sixtyfps_render::render_to_60fps};
-
-    use super::*;
+    use crate::{
+        bc_render::render_to_bc,
+        common::{
+            assert_leaves_cover, h_layout, h_line, h_split, invalidate_dimensions, tile, v_layout,
+            v_line, v_list, v_split, Highlight, Size,
+        },
+        display::DisplayProfile,
+        format::CType,
+        sixtyfps_render::render_to_60fps,
+    };
 
     #[test]
     fn activity_splash() {
+        let display = DisplayProfile::sharp_mip_2in7();
+
         let status_bar = h_layout([
-            tile("21:37").with_format("%T"),
+            tile("21:37").with_strftime("%T").bind("clock", CType::Time),
             v_line(),
-            tile("GPS 3D").with_format("GPS %1d"),
+            tile("GPS 3D")
+                .with_format("GPS %1d")
+                .bind("gps.fix", CType::U8),
             v_line(),
-            tile("02/09/21").with_format("%d/%m/%y"),
+            tile("02/09/21")
+                .with_strftime("%d/%m/%y")
+                .bind("clock", CType::Time),
         ]);
         let welcome_page = v_layout([h_line(), h_layout([tile("Activities")])]);
 
-        let mut gui = h_split(status_bar, 0.101, welcome_page);
+        let mut gui = v_layout([status_bar.with_height(Size::Pixels(24)), welcome_page]);
 
+        invalidate_dimensions(&mut gui, &display).unwrap();
 
-        invalidate_dimensions(&mut gui, &display_dimension);
+        assert_leaves_cover(&gui, &display.dim);
 
-        render_to_60fps(&gui, &display_dimension);
+        render_to_60fps(&gui, &display);
 
-        render_to_bc(&gui, &display_dimension);
+        render_to_bc(&gui, &display, "ActivitySplash");
     }
 
     #[test]
     fn select_activity() {
+        let display = DisplayProfile::sharp_mip_2in7();
+
         let status_bar = h_layout([
-            tile("21:37").with_format("%T"),
+            tile("21:37").with_strftime("%T").bind("clock", CType::Time),
             v_line(),
-            tile("GPS 3D").with_format("GPS %1d"),
+            tile("GPS 3D")
+                .with_format("GPS %1d")
+                .bind("gps.fix", CType::U8),
             v_line(),
-            tile("02/09/21").with_format("%d/%m/%y"),
+            tile("02/09/21")
+                .with_strftime("%d/%m/%y")
+                .bind("clock", CType::Time),
         ]);
         let welcome_page = v_layout([
             h_line(),
             v_line(),
-            h_layout([
+            v_split(
                 v_layout([tile("Activity"), h_line(), tile("")]),
+                0.4,
                 v_list([
                     "Running",
                     "Cycling",
@@ -50,55 +68,128 @@ mod test {
                     "Yoga",
                     "Swimming",
                 ])
-                .with_font_size(19),
-            ]),
+                .with_visible(4)
+                .with_font_size(31),
+            ),
         ]);
 
-        let mut gui = h_split(status_bar, 0.101, welcome_page);
+        let mut gui = v_layout([status_bar.with_height(Size::Pixels(24)), welcome_page]);
 
-        invalidate_dimensions(&mut gui, &display_dimension);
+        invalidate_dimensions(&mut gui, &display).unwrap();
 
-        render_to_60fps(&gui, &display_dimension);
+        assert_leaves_cover(&gui, &display.dim);
 
-        render_to_bc(&gui, &display_dimension);
+        render_to_60fps(&gui, &display);
+
+        render_to_bc(&gui, &display, "SelectActivity");
+    }
+
+    #[test]
+    fn select_activity_each_selection() {
+        let display = DisplayProfile::sharp_mip_2in7();
+
+        for selected in 0..6 {
+            let status_bar = h_layout([
+                tile("21:37").with_strftime("%T").bind("clock", CType::Time),
+                v_line(),
+                tile("GPS 3D")
+                    .with_format("GPS %1d")
+                    .bind("gps.fix", CType::U8),
+                v_line(),
+                tile("02/09/21")
+                    .with_strftime("%d/%m/%y")
+                    .bind("clock", CType::Time),
+            ]);
+            let welcome_page = v_layout([
+                h_line(),
+                v_line(),
+                v_split(
+                    v_layout([tile("Activity"), h_line(), tile("")]),
+                    0.4,
+                    v_list([
+                        "Running",
+                        "Cycling",
+                        "Hiking",
+                        "Ind. Cycling",
+                        "Yoga",
+                        "Swimming",
+                    ])
+                    .with_visible(4)
+                    .with_font_size(31)
+                    .with_selected(selected, Highlight::Inverted),
+                ),
+            ]);
+
+            let mut gui = v_layout([status_bar.with_height(Size::Pixels(24)), welcome_page]);
+
+            let report = invalidate_dimensions(&mut gui, &display).unwrap();
+
+            // Highlighted row has to fit as well as the others
+            assert_eq!(report.overflowed, vec![], "selected {}", selected);
+
+            assert_leaves_cover(&gui, &display.dim);
+
+            render_to_60fps(&gui, &display);
+
+            render_to_bc(&gui, &display, "SelectActivity");
+        }
     }
 
     #[test]
     fn select_running_workouts() {
+        let display = DisplayProfile::sharp_mip_2in7();
+
         let status_bar = h_layout([
-            tile("21:37").with_format("%T"),
+            tile("21:37").with_strftime("%T").bind("clock", CType::Time),
             v_line(),
-            tile("GPS 3D").with_format("GPS %1d"),
+            tile("GPS 3D")
+                .with_format("GPS %1d")
+                .bind("gps.fix", CType::U8),
             v_line(),
-            tile("02/09/21").with_format("%d/%m/%y"),
+            tile("02/09/21")
+                .with_strftime("%d/%m/%y")
+                .bind("clock", CType::Time),
         ]);
         let welcome_page = v_layout([
             h_line(),
             v_line(),
-            h_layout([
-                v_layout([tile("Workouts"), h_line(), tile("Running").with_font_size(31)]),
+            v_split(
+                v_layout([
+                    tile("Workouts"),
+                    h_line(),
+                    tile("Running").with_font_size(31),
+                ]),
+                0.4,
                 v_list(["5k", "10k", "Half Marathon", "Marathon", "Cooper Test"])
                     .with_font_size(24),
-            ]),
+            ),
         ]);
 
-        let mut gui = h_split(status_bar, 0.101, welcome_page);
+        let mut gui = v_layout([status_bar.with_height(Size::Pixels(24)), welcome_page]);
+
+        invalidate_dimensions(&mut gui, &display).unwrap();
 
-        invalidate_dimensions(&mut gui, &display_dimension);
+        assert_leaves_cover(&gui, &display.dim);
 
-        render_to_60fps(&gui, &display_dimension);
+        render_to_60fps(&gui, &display);
 
-        render_to_bc(&gui, &display_dimension);
+        render_to_bc(&gui, &display, "SelectRunningWorkouts");
     }
 
     #[test]
     fn activity_running_cooper_test() {
+        let display = DisplayProfile::sharp_mip_2in7();
+
         let status_bar = h_layout([
-            tile("21:37").with_format("%T"),
+            tile("21:37").with_strftime("%T").bind("clock", CType::Time),
             v_line(),
-            tile("GPS 3D").with_format("GPS %1d"),
+            tile("GPS 3D")
+                .with_format("GPS %1d")
+                .bind("gps.fix", CType::U8),
             v_line(),
-            tile("02/09/21").with_format("%d/%m/%y"),
+            tile("02/09/21")
+                .with_strftime("%d/%m/%y")
+                .bind("clock", CType::Time),
         ]);
         let welcome_page = v_layout([
             h_line(),
@@ -109,23 +200,31 @@ mod test {
             ]),
         ]);
 
-        let mut gui = h_split(status_bar, 0.101, welcome_page);
+        let mut gui = v_layout([status_bar.with_height(Size::Pixels(24)), welcome_page]);
+
+        invalidate_dimensions(&mut gui, &display).unwrap();
 
-        invalidate_dimensions(&mut gui, &display_dimension);
+        assert_leaves_cover(&gui, &display.dim);
 
-        render_to_60fps(&gui, &display_dimension);
+        render_to_60fps(&gui, &display);
 
-        render_to_bc(&gui, &display_dimension);
+        render_to_bc(&gui, &display, "ActivityRunningCooperTest");
     }
 
     #[test]
     fn activity_running_cooper_test_view() {
+        let display = DisplayProfile::sharp_mip_2in7();
+
         let status_bar = h_layout([
-            tile("21:37").with_format("%T"),
+            tile("21:37").with_strftime("%T").bind("clock", CType::Time),
             v_line(),
-            tile("GPS 3D").with_format("GPS %1d"),
+            tile("GPS 3D")
+                .with_format("GPS %1d")
+                .bind("gps.fix", CType::U8),
             v_line(),
-            tile("02/09/21").with_format("%d/%m/%y"),
+            tile("02/09/21")
+                .with_strftime("%d/%m/%y")
+                .bind("clock", CType::Time),
         ]);
         let welcome_page = v_layout([
             h_line(),
@@ -145,23 +244,31 @@ mod test {
             ),
         ]);
 
-        let mut gui = h_split(status_bar, 0.101, welcome_page);
+        let mut gui = v_layout([status_bar.with_height(Size::Pixels(24)), welcome_page]);
 
-        invalidate_dimensions(&mut gui, &display_dimension);
+        invalidate_dimensions(&mut gui, &display).unwrap();
 
-        render_to_60fps(&gui, &display_dimension);
+        assert_leaves_cover(&gui, &display.dim);
 
-        render_to_bc(&gui, &display_dimension);
+        render_to_60fps(&gui, &display);
+
+        render_to_bc(&gui, &display, "ActivityRunningCooperTest");
     }
 
     #[test]
     fn activity_running_do_it() {
+        let display = DisplayProfile::sharp_mip_2in7();
+
         let status_bar = h_layout([
-            tile("21:37").with_format("%T"),
+            tile("21:37").with_strftime("%T").bind("clock", CType::Time),
             v_line(),
-            tile("GPS 3D").with_format("GPS %1d"),
+            tile("GPS 3D")
+                .with_format("GPS %1d")
+                .bind("gps.fix", CType::U8),
             v_line(),
-            tile("02/09/21").with_format("%d/%m/%y"),
+            tile("02/09/21")
+                .with_strftime("%d/%m/%y")
+                .bind("clock", CType::Time),
         ]);
         let welcome_page = v_layout([
             h_line(),
@@ -177,12 +284,14 @@ mod test {
             ]),
         ]);
 
-        let mut gui = h_split(status_bar, 0.101, welcome_page);
+        let mut gui = v_layout([status_bar.with_height(Size::Pixels(24)), welcome_page]);
+
+        invalidate_dimensions(&mut gui, &display).unwrap();
 
-        invalidate_dimensions(&mut gui, &display_dimension);
+        assert_leaves_cover(&gui, &display.dim);
 
-        render_to_60fps(&gui, &display_dimension);
+        render_to_60fps(&gui, &display);
 
-        render_to_bc(&gui, &display_dimension);
+        render_to_bc(&gui, &display, "ActivityRunningDoIt");
     }
 }
@@ -3,43 +3,56 @@ mod test {
     use crate::{
         bc_render::render_to_bc,
         common::{
-            h_layout, h_line, h_split, invalidate_dimensions, tile, v_layout, v_line, v_list,
-            Dimension,
+            assert_leaves_cover, h_layout, h_line, h_split, invalidate_dimensions, tile, v_layout,
+            v_line, v_list, Size,
         },
-        mocks_sharp_mip_2in7::common_params::display_dimension,
+        display::DisplayProfile,
+        format::CType,
         sixtyfps_render::render_to_60fps,
     };
 
-    use super::*;
-
     #[test]
     fn statistics_splash() {
+        let display = DisplayProfile::sharp_mip_2in7();
+
         let status_bar = h_layout([
-            tile("21:37").with_format("%T"),
+            tile("21:37").with_strftime("%T").bind("clock", CType::Time),
             v_line(),
-            tile("GPS 3D").with_format("GPS %1d"),
+            tile("GPS 3D")
+                .with_format("GPS %1d")
+                .bind("gps.fix", CType::U8),
             v_line(),
-            tile("02/09/21").with_format("%d/%m/%y"),
+            tile("02/09/21")
+                .with_strftime("%d/%m/%y")
+                .bind("clock", CType::Time),
         ]);
         let welcome_page = v_layout([h_line(), h_layout([tile("Statistics")])]);
 
-        let mut gui = h_split(status_bar, 0.101, welcome_page);
+        let mut gui = v_layout([status_bar.with_height(Size::Pixels(24)), welcome_page]);
 
-        invalidate_dimensions(&mut gui, &display_dimension);
+        invalidate_dimensions(&mut gui, &display).unwrap();
 
-        render_to_60fps(&gui, &display_dimension);
+        assert_leaves_cover(&gui, &display.dim);
 
-        render_to_bc(&gui, &display_dimension);
+        render_to_60fps(&gui, &display);
+
+        render_to_bc(&gui, &display, "StatisticsSplash");
     }
 
     #[test]
     fn select_stats() {
+        let display = DisplayProfile::sharp_mip_2in7();
+
         let status_bar = h_layout([
-            tile("21:37").with_format("%T"),
+            tile("21:37").with_strftime("%T").bind("clock", CType::Time),
             v_line(),
-            tile("GPS 3D").with_format("GPS %1d"),
+            tile("GPS 3D")
+                .with_format("GPS %1d")
+                .bind("gps.fix", CType::U8),
             v_line(),
-            tile("02/09/21").with_format("%d/%m/%y"),
+            tile("02/09/21")
+                .with_strftime("%d/%m/%y")
+                .bind("clock", CType::Time),
         ]);
         let welcome_page = v_layout([
             h_line(),
@@ -50,23 +63,31 @@ mod test {
             ]),
         ]);
 
-        let mut gui = h_split(status_bar, 0.101, welcome_page);
+        let mut gui = v_layout([status_bar.with_height(Size::Pixels(24)), welcome_page]);
 
-        invalidate_dimensions(&mut gui, &display_dimension);
+        invalidate_dimensions(&mut gui, &display).unwrap();
 
-        render_to_60fps(&gui, &display_dimension);
+        assert_leaves_cover(&gui, &display.dim);
 
-        render_to_bc(&gui, &display_dimension);
+        render_to_60fps(&gui, &display);
+
+        render_to_bc(&gui, &display, "SelectStats");
     }
 
     #[test]
     fn stats_selected() {
+        let display = DisplayProfile::sharp_mip_2in7();
+
         let status_bar = h_layout([
-            tile("21:37").with_format("%T"),
+            tile("21:37").with_strftime("%T").bind("clock", CType::Time),
             v_line(),
-            tile("GPS 3D").with_format("GPS %1d"),
+            tile("GPS 3D")
+                .with_format("GPS %1d")
+                .bind("gps.fix", CType::U8),
             v_line(),
-            tile("02/09/21").with_format("%d/%m/%y"),
+            tile("02/09/21")
+                .with_strftime("%d/%m/%y")
+                .bind("clock", CType::Time),
         ]);
         let welcome_page = v_layout([
             h_line(),
@@ -86,12 +107,14 @@ mod test {
             ),
         ]);
 
-        let mut gui = h_split(status_bar, 0.101, welcome_page);
+        let mut gui = v_layout([status_bar.with_height(Size::Pixels(24)), welcome_page]);
+
+        invalidate_dimensions(&mut gui, &display).unwrap();
 
-        invalidate_dimensions(&mut gui, &display_dimension);
+        assert_leaves_cover(&gui, &display.dim);
 
-        render_to_60fps(&gui, &display_dimension);
+        render_to_60fps(&gui, &display);
 
-        render_to_bc(&gui, &display_dimension);
+        render_to_bc(&gui, &display, "StatsSelected");
     }
 }
@@ -1,5 +1,5 @@
+mod activity_paused;
+mod running;
 mod select_activity;
-mod welcome;
 mod stats;
-mod running;
-mod activity_paused;
\ No newline at end of file
+mod welcome;
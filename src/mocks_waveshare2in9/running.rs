@@ -3,28 +3,35 @@ mod test {
     use crate::{
         bc_render::render_to_bc,
         common::{
-            h_layout, h_line, h_split, invalidate_dimensions, tile, v_layout, v_line, v_list,
-            Dimension,
+            assert_leaves_cover, h_layout, h_line, invalidate_dimensions, tile, v_layout, v_line,
+            v_list, Size,
         },
+        display::DisplayProfile,
+        format::CType,
         sixtyfps_render::render_to_60fps,
     };
 
-    use super::*;
-
     #[test]
     fn page_1() {
         let status_bar = h_layout([
-            tile("21:37").with_format("%T"),
+            tile("21:37").with_strftime("%T").bind("clock", CType::Time),
             v_line(),
-            tile("GPS 3D").with_format("GPS %1d"),
+            tile("GPS 3D")
+                .with_format("GPS %1d")
+                .bind("gps.fix", CType::U8),
             v_line(),
-            tile("02/09/21").with_format("%d/%m/%y"),
+            tile("02/09/21")
+                .with_strftime("%d/%m/%y")
+                .bind("clock", CType::Time),
         ]);
         let welcome_page = h_layout([
             v_layout([
                 v_layout([
                     tile("pace").with_font_size(16),
-                    tile("10.20").with_format("%.2f").with_font_size(20),
+                    tile("10.20")
+                        .with_format("%.2f")
+                        .bind("pace", CType::F32)
+                        .with_font_size(20),
                 ]),
                 h_line(),
                 v_layout([
@@ -32,11 +39,17 @@ mod test {
                     // tile("1.23").with_format("%.2f").with_font_size(20),
                     h_layout([
                         tile("lap time").with_font_size(16),
-                        tile("02:03:04").with_format("%.2f").with_font_size(16),
+                        tile("02:03:04")
+                            .with_duration("%T")
+                            .bind("lap_time", CType::U32)
+                            .with_font_size(16),
                     ]),
                     h_layout([
                         tile("lap dist").with_font_size(16),
-                        tile("21.37").with_format("%.2f").with_font_size(20),
+                        tile("21.37")
+                            .with_format("%.2f")
+                            .bind("lap_dist", CType::F32)
+                            .with_font_size(20),
                     ]),
                 ]),
             ]),
@@ -44,118 +57,139 @@ mod test {
             v_layout([
                 v_layout([
                     tile("HR zone").with_font_size(16),
-                    tile("2.79").with_format("%.2f").with_font_size(20),
+                    tile("2.79")
+                        .with_format("%.2f")
+                        .bind("hr_zone", CType::F32)
+                        .with_font_size(20),
                 ]),
                 h_line(),
                 v_layout([
                     tile("cadence").with_font_size(16),
-                    tile("158").with_format("%3d").with_font_size(20),
+                    tile("158")
+                        .with_format("%3d")
+                        .bind("cadence", CType::U16)
+                        .with_font_size(20),
                 ]),
             ]),
         ]);
 
-        let mut gui = h_split(status_bar, 0.101, welcome_page);
+        let mut gui = v_layout([status_bar.with_height(Size::Pixels(12)), welcome_page]);
+
+        let display = DisplayProfile::waveshare_2in9();
 
-        let d = Dimension {
-            x: 0,
-            y: 0,
-            width: 296,
-            height: 128,
-        };
+        invalidate_dimensions(&mut gui, &display).unwrap();
 
-        invalidate_dimensions(&mut gui, &d);
+        assert_leaves_cover(&gui, &display.dim);
 
-        render_to_60fps(&gui, &d);
+        render_to_60fps(&gui, &display);
 
-        render_to_bc(&gui, &d);
+        render_to_bc(&gui, &display, "RunningPage1");
     }
 
     #[test]
     fn page_2() {
         let status_bar = h_layout([
-            tile("21:37").with_format("%T"),
+            tile("21:37").with_strftime("%T").bind("clock", CType::Time),
             v_line(),
-            tile("GPS 3D").with_format("GPS %1d"),
+            tile("GPS 3D")
+                .with_format("GPS %1d")
+                .bind("gps.fix", CType::U8),
             v_line(),
-            tile("02/09/21").with_format("%d/%m/%y"),
+            tile("02/09/21")
+                .with_strftime("%d/%m/%y")
+                .bind("clock", CType::Time),
         ]);
         let welcome_page = h_layout([
             v_layout([
                 v_layout([
                     tile("total dist").with_font_size(16),
-                    tile("10.20").with_format("%.2f").with_font_size(20),
+                    tile("10.20")
+                        .with_format("%.2f")
+                        .bind("total_dist", CType::F32)
+                        .with_font_size(20),
                 ]),
                 h_line(),
                 v_layout([
                     tile("lap dist").with_font_size(16),
-                    tile("5.20").with_format("%.2f").with_font_size(20),
+                    tile("5.20")
+                        .with_format("%.2f")
+                        .bind("lap_dist", CType::F32)
+                        .with_font_size(20),
                 ]),
             ]),
             v_line(),
             v_layout([
                 v_layout([
                     tile("total time").with_font_size(16),
-                    tile("02:12:20").with_format("%T").with_font_size(20),
+                    tile("02:12:20")
+                        .with_duration("%T")
+                        .bind("total_time", CType::U32)
+                        .with_font_size(20),
                 ]),
                 h_line(),
                 v_layout([
                     tile("lap time").with_font_size(16),
-                    tile("01:12:20").with_format("%T").with_font_size(20),
+                    tile("01:12:20")
+                        .with_duration("%T")
+                        .bind("lap_time", CType::U32)
+                        .with_font_size(20),
                 ]),
             ]),
         ]);
 
-        let mut gui = h_split(status_bar, 0.101, welcome_page);
+        let mut gui = v_layout([status_bar.with_height(Size::Pixels(12)), welcome_page]);
+
+        let display = DisplayProfile::waveshare_2in9();
 
-        let d = Dimension {
-            x: 0,
-            y: 0,
-            width: 296,
-            height: 128,
-        };
+        invalidate_dimensions(&mut gui, &display).unwrap();
 
-        invalidate_dimensions(&mut gui, &d);
+        assert_leaves_cover(&gui, &display.dim);
 
-        render_to_60fps(&gui, &d);
+        render_to_60fps(&gui, &display);
 
-        render_to_bc(&gui, &d);
+        render_to_bc(&gui, &display, "RunningPage2");
     }
 
     #[test]
     fn workout_steps_splash() {
         let status_bar = h_layout([
-            tile("21:37").with_format("%T"),
+            tile("21:37").with_strftime("%T").bind("clock", CType::Time),
             v_line(),
-            tile("GPS 3D").with_format("GPS %1d"),
+            tile("GPS 3D")
+                .with_format("GPS %1d")
+                .bind("gps.fix", CType::U8),
             v_line(),
-            tile("02/09/21").with_format("%d/%m/%y"),
+            tile("02/09/21")
+                .with_strftime("%d/%m/%y")
+                .bind("clock", CType::Time),
         ]);
         let welcome_page = v_layout([h_line(), h_layout([tile("Workout Steps")])]);
 
-        let mut gui = h_split(status_bar, 0.101, welcome_page);
+        let mut gui = v_layout([status_bar.with_height(Size::Pixels(12)), welcome_page]);
 
-        let d = Dimension {
-            x: 0,
-            y: 0,
-            width: 296,
-            height: 128,
-        };
+        let display = DisplayProfile::waveshare_2in9();
 
-        invalidate_dimensions(&mut gui, &d);
+        invalidate_dimensions(&mut gui, &display).unwrap();
 
-        render_to_60fps(&gui, &d);
+        assert_leaves_cover(&gui, &display.dim);
 
-        render_to_bc(&gui, &d);
+        render_to_60fps(&gui, &display);
+
+        render_to_bc(&gui, &display, "WorkoutStepsSplash");
     }
 
     #[test]
     fn page_3() {
         let status_bar = h_layout([
-            tile("21:37").with_format("%T"),
+            tile("21:37").with_strftime("%T").bind("clock", CType::Time),
             v_line(),
-            tile("GPS 3D").with_format("GPS %1d"),
+            tile("GPS 3D")
+                .with_format("GPS %1d")
+                .bind("gps.fix", CType::U8),
             v_line(),
-            tile("02/09/21").with_format("%d/%m/%y"),
+            tile("02/09/21")
+                .with_strftime("%d/%m/%y")
+                .bind("clock", CType::Time),
         ]);
         let welcome_page = h_layout([v_list([
             "run 5.00 1/5",
@@ -165,30 +199,31 @@ mod test {
         ])
         .with_font_size(16)]);
 
-        let mut gui = h_split(status_bar, 0.101, welcome_page);
+        let mut gui = v_layout([status_bar.with_height(Size::Pixels(12)), welcome_page]);
+
+        let display = DisplayProfile::waveshare_2in9();
 
-        let d = Dimension {
-            x: 0,
-            y: 0,
-            width: 296,
-            height: 128,
-        };
+        invalidate_dimensions(&mut gui, &display).unwrap();
 
-        invalidate_dimensions(&mut gui, &d);
+        assert_leaves_cover(&gui, &display.dim);
 
-        render_to_60fps(&gui, &d);
+        render_to_60fps(&gui, &display);
 
-        render_to_bc(&gui, &d);
+        render_to_bc(&gui, &display, "RunningPage3");
     }
 
     #[test]
     fn page_paused() {
         let status_bar = h_layout([
-            tile("21:37").with_format("%T"),
+            tile("21:37").with_strftime("%T").bind("clock", CType::Time),
             v_line(),
-            tile("GPS 3D").with_format("GPS %1d"),
+            tile("GPS 3D")
+                .with_format("GPS %1d")
+                .bind("gps.fix", CType::U8),
             v_line(),
-            tile("02/09/21").with_format("%d/%m/%y"),
+            tile("02/09/21")
+                .with_strftime("%d/%m/%y")
+                .bind("clock", CType::Time),
         ]);
         let welcome_page = v_layout([
             h_line(),
@@ -199,19 +234,16 @@ mod test {
             ]),
         ]);
 
-        let mut gui = h_split(status_bar, 0.101, welcome_page);
+        let mut gui = v_layout([status_bar.with_height(Size::Pixels(12)), welcome_page]);
+
+        let display = DisplayProfile::waveshare_2in9();
 
-        let d = Dimension {
-            x: 0,
-            y: 0,
-            width: 296,
-            height: 128,
-        };
+        invalidate_dimensions(&mut gui, &display).unwrap();
 
-        invalidate_dimensions(&mut gui, &d);
+        assert_leaves_cover(&gui, &display.dim);
 
-        render_to_60fps(&gui, &d);
+        render_to_60fps(&gui, &display);
 
-        render_to_bc(&gui, &d);
+        render_to_bc(&gui, &display, "RunningPaused");
     }
 }
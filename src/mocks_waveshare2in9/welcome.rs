@@ -3,44 +3,50 @@ mod test {
     use crate::{
         bc_render::render_to_bc,
         common::{
-            h_layout, h_line, h_split, invalidate_dimensions, tile, v_layout, v_line, v_list,
-            Dimension,
+            assert_leaves_cover, h_layout, h_line, invalidate_dimensions, tile, v_layout, v_line,
+            Size,
         },
+        display::DisplayProfile,
+        format::CType,
         sixtyfps_render::render_to_60fps,
     };
 
-    use super::*;
     #[test]
     fn welcome() {
         let status_bar = h_layout([
-            tile("21:37").with_format("%T"),
+            tile("21:37").with_strftime("%T").bind("clock", CType::Time),
             v_line(),
-            tile("GPS 3D").with_format("GPS %1d"),
+            tile("GPS 3D")
+                .with_format("GPS %1d")
+                .bind("gps.fix", CType::U8),
             v_line(),
-            tile("02/09/21").with_format("%d/%m/%y"),
+            tile("02/09/21")
+                .with_strftime("%d/%m/%y")
+                .bind("clock", CType::Time),
         ]);
         let welcome_page = v_layout([
             h_line(),
             h_layout([v_layout([
-                tile("21:37:07").with_format("%T"),
+                tile("21:37:07")
+                    .with_strftime("%T")
+                    .bind("clock", CType::Time),
                 h_line(),
-                tile("02/09/21").with_format("%d/%m/%y"),
+                tile("02/09/21")
+                    .with_strftime("%d/%m/%y")
+                    .bind("clock", CType::Time),
             ])]),
         ]);
 
-        let mut gui = h_split(status_bar, 0.101, welcome_page);
+        let mut gui = v_layout([status_bar.with_height(Size::Pixels(12)), welcome_page]);
 
-        let d = Dimension {
-            x: 0,
-            y: 0,
-            width: 296,
-            height: 128,
-        };
+        let display = DisplayProfile::waveshare_2in9();
 
-        invalidate_dimensions(&mut gui, &d);
+        invalidate_dimensions(&mut gui, &display).unwrap();
 
-        render_to_60fps(&gui, &d);
+        assert_leaves_cover(&gui, &display.dim);
 
-        render_to_bc(&gui, &d);
+        render_to_60fps(&gui, &display);
+
+        render_to_bc(&gui, &display, "Welcome");
     }
 }
@@ -1,27 +1,44 @@
-use crate::common::{self, Dimension, Node, Tile};
+use crate::{
+    common::{HAlign, Highlight, List, Node, Tile},
+    display::DisplayProfile,
+};
+
+/// Width of the bar showing which part of the scrolled list is visible
+const SCROLL_INDICATOR_WIDTH: usize = 3;
+
+fn render_60fps_rectangle(tile: &Tile, highlight: Option<Highlight>) -> String {
+    let content = tile.content();
+    let (background, color, border_width) = match highlight {
+        None => ("silver", "black", 0),
+        Some(Highlight::Inverted) => ("black", "white", 0),
+        Some(Highlight::Frame) => ("silver", "black", 1),
+    };
 
-fn render_60fps_rectangle(tile: &Tile) -> String {
     format!(
         r#"Rectangle {{
     x: {x}phx;
     y: {y}phx;
     width: {width}phx;
     height: {height}phx;
-    background: silver;
+    background: {background};
     border-color: black;
-    border-width: 0px;
+    border-width: {border_width}px;
     Text {{
-        //x: {{x_text}}phx;
+        // Fonts differ significantly between 60fps and BC display,
+        // so align inside the content, instead of using x of the text
+        x: {x_content}phx;
         y: {y_text}phx;
-        width: 100%;
+        width: {content_width}phx;
         height: 100%;
         text: "{name}";
         font-size: {font_size}phx;
+        color: {color};
         // That's the closest font to the one on BC display,
         // still very different
         font-family: "Ubuntu Mono";
-        // vertical-alignment: center;
-        horizontal-alignment: center;
+        vertical-alignment: top;
+        horizontal-alignment: {h_align};
+        wrap: {wrap};
     }}
 }}
 "#,
@@ -29,27 +46,60 @@ fn render_60fps_rectangle(tile: &Tile) -> String {
         y = tile.dim.y,
         width = tile.dim.width,
         height = tile.dim.height,
-        // Use horizontal-alignment, since fonts differ significantly
-        // between 60fps and BC display
-        // x_text = tile.text.dim.x,
+        background = background,
+        border_width = border_width,
+        color = color,
+        x_content = content.x - tile.dim.x,
+        content_width = content.width,
         y_text = tile.text.dim.y,
-        name = tile.text.name,
-        font_size = tile.text.font_size.unwrap()
+        name = tile.text.shown(),
+        font_size = tile.text.font.unwrap(),
+        h_align = match tile.text.h_align {
+            HAlign::Left => "left",
+            HAlign::Center => "center",
+            HAlign::Right => "right",
+        },
+        wrap = match tile.text.wrap {
+            true => "word-wrap",
+            false => "no-wrap",
+        }
     )
 }
+/// Bar on the right edge of the list, as long as the visible part of it
+fn render_60fps_scroll_indicator(list: &List) -> String {
+    let content = list.content();
+    let length = content.height * list.visible_elements / list.elements.len();
+    let offset = content.height * list.visible_range().start / list.elements.len();
+
+    format!(
+        r#"Rectangle {{
+    x: {x}phx;
+    y: {y}phx;
+    width: {width}phx;
+    height: {height}phx;
+    background: black;
+}}
+"#,
+        x = content.x + content.width - SCROLL_INDICATOR_WIDTH,
+        y = content.y + offset,
+        width = SCROLL_INDICATOR_WIDTH,
+        height = length
+    )
+}
+
 /// Returns a tuple (Dynamic, Static) widgets
 fn render_60fps_widgets(root: &Node) -> (String, String) {
     match root {
-        Node::V(nodes) | Node::H(nodes) => nodes
-            .iter()
-            .map(|node| render_60fps_widgets(node))
-            .fold((String::default(), String::default()), |mut acc, x| {
+        Node::V(layout) | Node::H(layout) => layout.nodes.iter().map(render_60fps_widgets).fold(
+            (String::default(), String::default()),
+            |mut acc, x| {
                 acc.0 += &x.0;
                 acc.1 += &x.1;
 
                 acc
-            }),
-        Node::HH(split) => {
+            },
+        ),
+        Node::HH(split) | Node::VV(split) => {
             let (l_dyn, l_stat) = render_60fps_widgets(&split.first);
             let (r_dyn, r_stat) = render_60fps_widgets(&split.second);
 
@@ -57,8 +107,12 @@ fn render_60fps_widgets(root: &Node) -> (String, String) {
             (l_dyn + &r_dyn, l_stat + &r_stat)
         }
 
-        Node::Tile(tile) => (render_60fps_rectangle(tile), String::default()),
-        Node::HorizontalLine(dim) | Node::VerticalLine(dim) => (
+        Node::Tile(tile) if tile.text.is_dynamic() => {
+            (render_60fps_rectangle(tile, None), String::default())
+        }
+        // Labels go to the static layer, with the lines
+        Node::Tile(tile) => (String::default(), render_60fps_rectangle(tile, None)),
+        Node::HorizontalLine(line) | Node::VerticalLine(line) => (
             String::default(),
             format!(
                 r#"Rectangle {{
@@ -71,24 +125,32 @@ fn render_60fps_widgets(root: &Node) -> (String, String) {
             border-width: 0px;
         }}
         "#,
-                x = dim.x,
-                y = dim.y,
-                width = dim.width,
-                height = dim.height,
+                x = line.dim.x,
+                y = line.dim.y,
+                width = line.dim.width,
+                height = line.dim.height,
             ),
         ),
-        Node::VListWidget(list) => (
-            list.elements.iter().fold(String::new(), |acc, tile| {
-                acc + &render_60fps_rectangle(tile)
-            }),
-            String::default(),
-        ),
+        Node::VListWidget(list) => {
+            // Preview shows the page with the selected element
+            let mut elements = list.visible_range().fold(String::new(), |acc, idx| {
+                let highlight = Some(list.highlight).filter(|_| list.selected == Some(idx));
+                acc + &render_60fps_rectangle(&list.elements[idx], highlight)
+            });
+
+            if list.elements.len() > list.visible_elements {
+                elements += &render_60fps_scroll_indicator(list);
+            }
+
+            (elements, String::default())
+        }
     }
 }
 
 /// Gets gui layout and creates a sixty fps markup String representing that layout.
-pub fn render_to_60fps(root: &Node, d: &Dimension) -> String {
-    let (tiles, static_elements) = render_60fps_widgets(&root);
+pub fn render_to_60fps(root: &Node, display: &DisplayProfile) -> String {
+    let d = &display.dim;
+    let (tiles, static_elements) = render_60fps_widgets(root);
 
     let result = format!(
         "MainWindow := Window{{
@@ -109,6 +171,5 @@ pub fn render_to_60fps(root: &Node, d: &Dimension) -> String {
 
     // println!("Rendered:\n {}", result);
 
-    common::write_to_file(&result, "ui/main.60");
     result
-}
\ No newline at end of file
+}